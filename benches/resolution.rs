@@ -0,0 +1,50 @@
+//! Compares resolving directories on every call with the process-wide cache.
+//!
+//! Run with `cargo bench`.
+
+extern crate directories;
+
+use std::hint::black_box;
+use std::time::Duration;
+use std::time::Instant;
+
+use directories::BaseDirectories;
+use directories::DirectoryKind;
+use directories::ProjectDirectories;
+
+const ITERATIONS: u32 = 10_000;
+
+fn measure<F: Fn()>(name: &str, f: F) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_call = start.elapsed() / ITERATIONS;
+    println!("{:<26} {:>10?} per call", name, per_call);
+    per_call
+}
+
+fn main() {
+    let dirs = BaseDirectories::new();
+    let uncached = measure("get (uncached)", || {
+        black_box(dirs.get(black_box(DirectoryKind::Cache)));
+    });
+    let cached = measure("cached().get", || {
+        black_box(BaseDirectories::cached().get(black_box(DirectoryKind::Cache)).map(|dir| dir.to_path_buf()));
+    });
+    let project_uncached = measure("from_project_name", || {
+        black_box(ProjectDirectories::from_project_name(black_box("Foo App")).project_cache_dir().to_path_buf());
+    });
+    let project_cached = measure("from_project_name_cached", || {
+        black_box(ProjectDirectories::from_project_name_cached(black_box("Foo App")).project_cache_dir().to_path_buf());
+    });
+    measure("explain (uncached)", || {
+        black_box(dirs.explain());
+    });
+    measure("refresh", || {
+        black_box(BaseDirectories::refresh());
+    });
+    println!("speedup for a single directory: {:.1}x", uncached.as_secs_f64() / cached.as_secs_f64());
+    println!("speedup for project directories: {:.1}x", project_uncached.as_secs_f64() / project_cached.as_secs_f64());
+}
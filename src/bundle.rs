@@ -0,0 +1,153 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use layout::PlatformFolders;
+use BaseDirectories;
+
+/// The per-application directories inside `~/Library` on macOS.
+///
+/// All directories are computed from the home directory and the bundle
+/// identifier of the application, e. g. `com.example.App`, without consulting
+/// the operating system, so they can be computed on any host.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MacAppDirectories {
+    bundle_id: String,
+    home_dir: PathBuf,
+}
+
+/// The reasons a bundle or group identifier is refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleIdError {
+    /// The identifier is empty.
+    Empty,
+    /// The identifier contains a character other than ASCII letters, digits, `-` and `.`.
+    InvalidCharacter(String, char),
+    /// The identifier starts or ends with `.`, contains `..`, or is not in reverse-DNS form.
+    Malformed(String),
+}
+
+#[deny(missing_docs)]
+impl MacAppDirectories {
+    /// Computes the directories of the application with the given bundle
+    /// identifier, below the home directory of the current user.
+    pub fn from_bundle_id(bundle_id: &str) -> Result<MacAppDirectories, BundleIdError> {
+        MacAppDirectories::from_bundle_id_with_home(bundle_id, BaseDirectories::home_dir())
+    }
+
+    /// Computes the directories of the application with the given bundle
+    /// identifier, below the given home directory.
+    pub fn from_bundle_id_with_home<P: Into<PathBuf>>(bundle_id: &str, home_dir: P) -> Result<MacAppDirectories, BundleIdError> {
+        validate_identifier(bundle_id)?;
+        Ok(MacAppDirectories { bundle_id: String::from(bundle_id), home_dir: home_dir.into() })
+    }
+
+    /// Returns the bundle identifier.
+    pub fn bundle_id(&self) -> &str {
+        self.bundle_id.as_str()
+    }
+
+    /// Returns the log directory, e. g. `~/Library/Logs/com.example.App`.
+    pub fn logs_dir(&self) -> PathBuf {
+        self.home_dir.join("Library/Logs").join(&self.bundle_id)
+    }
+
+    /// Returns the directory of the window state restored on relaunch, e. g.
+    /// `~/Library/Saved Application State/com.example.App.savedState`.
+    pub fn saved_state_dir(&self) -> PathBuf {
+        self.home_dir
+            .join("Library/Saved Application State")
+            .join(format!("{}.savedState", self.bundle_id))
+    }
+
+    /// Returns the sandbox container, e. g. `~/Library/Containers/com.example.App`.
+    pub fn container_dir(&self) -> PathBuf {
+        self.home_dir.join("Library/Containers").join(&self.bundle_id)
+    }
+
+    /// Returns the home directory of the application inside its sandbox container,
+    /// e. g. `~/Library/Containers/com.example.App/Data`.
+    pub fn container_data_dir(&self) -> PathBuf {
+        self.container_dir().join("Data")
+    }
+
+    /// Returns the inputs of the layout a sandboxed application sees.
+    ///
+    /// Passing the result to `Platform::MacOs` yields e. g. the cache directory
+    /// `~/Library/Containers/com.example.App/Data/Library/Caches`.
+    pub fn container_folders(&self) -> PlatformFolders {
+        PlatformFolders::new(self.container_data_dir())
+    }
+
+    /// Returns the container shared by an application group, e. g.
+    /// `~/Library/Group Containers/group.com.example.shared`.
+    ///
+    /// The group identifier is validated like a bundle identifier; it is usually
+    /// prefixed by `group.` or by the team identifier.
+    pub fn group_container_dir(&self, group_id: &str) -> Result<PathBuf, BundleIdError> {
+        validate_identifier(group_id)?;
+        Ok(self.home_dir.join("Library/Group Containers").join(group_id))
+    }
+}
+
+impl fmt::Display for BundleIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BundleIdError::Empty => f.write_str("bundle identifier is empty"),
+            BundleIdError::InvalidCharacter(ref id, c) => {
+                write!(f, "bundle identifier {:?} contains invalid character {:?}", id, c)
+            }
+            BundleIdError::Malformed(ref id) => {
+                write!(f, "bundle identifier {:?} is not in reverse-DNS form", id)
+            }
+        }
+    }
+}
+
+impl Error for BundleIdError {}
+
+/// Checks that `id` consists of at least two non-empty components separated by
+/// `.`, using only the characters Apple allows in bundle identifiers.
+fn validate_identifier(id: &str) -> Result<(), BundleIdError> {
+    if id.is_empty() {
+        return Err(BundleIdError::Empty);
+    }
+    if let Some(c) = id.chars().find(|&c| !(c.is_ascii_alphanumeric() || c == '-' || c == '.')) {
+        return Err(BundleIdError::InvalidCharacter(String::from(id), c));
+    }
+    if !id.contains('.') || id.split('.').any(str::is_empty) {
+        return Err(BundleIdError::Malformed(String::from(id)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use bundle::BundleIdError;
+    use bundle::MacAppDirectories;
+    use kind::DirectoryKind;
+    use layout::Platform;
+
+    #[test]
+    fn test_mac_app_directories() {
+        let dirs = MacAppDirectories::from_bundle_id_with_home("com.example.App", "/Users/eve").unwrap();
+        assert_eq!(dirs.logs_dir(), PathBuf::from("/Users/eve/Library/Logs/com.example.App"));
+        let expected = PathBuf::from("/Users/eve/Library/Saved Application State/com.example.App.savedState");
+        assert_eq!(dirs.saved_state_dir(), expected);
+        assert_eq!(dirs.container_data_dir(), PathBuf::from("/Users/eve/Library/Containers/com.example.App/Data"));
+        let actual = Platform::MacOs.base_dir(DirectoryKind::Cache, &dirs.container_folders());
+        assert_eq!(actual, Some(PathBuf::from("/Users/eve/Library/Containers/com.example.App/Data/Library/Caches")));
+        assert_eq!(
+            dirs.group_container_dir("group.com.example.shared"),
+            Ok(PathBuf::from("/Users/eve/Library/Group Containers/group.com.example.shared"))
+        );
+
+        let invalid = |id: &str| MacAppDirectories::from_bundle_id_with_home(id, "/Users/eve").unwrap_err();
+        assert_eq!(invalid(""), BundleIdError::Empty);
+        assert_eq!(invalid("com.example/../App"), BundleIdError::InvalidCharacter(String::from("com.example/../App"), '/'));
+        assert_eq!(invalid("com..example"), BundleIdError::Malformed(String::from("com..example")));
+        assert_eq!(invalid("App"), BundleIdError::Malformed(String::from("App")));
+        assert!(dirs.group_container_dir("group shared").is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use snapshot::DirectorySnapshot;
+use BaseDirectories;
+
+static CACHE: RwLock<Option<Arc<DirectorySnapshot>>> = RwLock::new(None);
+
+impl BaseDirectories {
+    /// Returns the directories resolved on first use, shared by all threads.
+    ///
+    /// The environment is read only once per process, which makes this suitable
+    /// for hot paths. Programs that modify their environment afterwards have to
+    /// call `refresh` to make the change visible.
+    pub fn cached() -> Arc<DirectorySnapshot> {
+        if let Some(ref snapshot) = *CACHE.read().unwrap_or_else(|err| err.into_inner()) {
+            return Arc::clone(snapshot);
+        }
+        let mut cache = CACHE.write().unwrap_or_else(|err| err.into_inner());
+        Arc::clone(cache.get_or_insert_with(|| Arc::new(DirectorySnapshot::new())))
+    }
+
+    /// Resolves the directories anew and replaces the result returned by `cached`.
+    ///
+    /// Snapshots obtained from `cached` before keep their values.
+    pub fn refresh() -> Arc<DirectorySnapshot> {
+        let snapshot = Arc::new(DirectorySnapshot::new());
+        *CACHE.write().unwrap_or_else(|err| err.into_inner()) = Some(Arc::clone(&snapshot));
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use BaseDirectories;
+
+    #[test]
+    fn test_cached_and_refresh() {
+        let threads: Vec<_> = (0..4).map(|_| thread::spawn(BaseDirectories::cached)).collect();
+        let first = BaseDirectories::cached();
+        for thread in threads {
+            assert!(Arc::ptr_eq(&thread.join().unwrap(), &first));
+        }
+
+        let refreshed = BaseDirectories::refresh();
+        assert!(!Arc::ptr_eq(&refreshed, &first));
+        assert!(Arc::ptr_eq(&BaseDirectories::cached(), &refreshed));
+        assert_eq!(*refreshed, *first);
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use kind::DirectoryKind;
+use snapshot::DirectorySnapshot;
+use ProjectDirectories;
+
+/// The result of `classify`: which directory a path lies in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Classification {
+    kind: DirectoryKind,
+    project_name: Option<String>,
+    dir: PathBuf,
+}
+
+#[deny(missing_docs)]
+impl Classification {
+    /// Returns the kind of the most specific directory containing the path.
+    pub fn kind(&self) -> DirectoryKind {
+        self.kind
+    }
+
+    /// Returns the name of the project, if the path lies inside one of its directories.
+    pub fn project_name(&self) -> Option<&str> {
+        self.project_name.as_deref()
+    }
+
+    /// Returns the (normalized) directory the path has been matched against.
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+}
+
+/// Returns the kind of the most specific base directory containing `path`.
+///
+/// Both `path` and the base directories are normalized lexically first, i. e.
+/// `.` components are dropped and `..` components remove their predecessor;
+/// symbolic links are not resolved. Nested directories are attributed to the
+/// innermost one, so a file in `font_dir` is classified as `DirectoryKind::Font`
+/// even though `font_dir` lies inside `data_dir` on Linux. If several kinds share
+/// the same directory, the first one in declaration order of `DirectoryKind` wins.
+///
+/// The base directories are taken from a `DirectorySnapshot`, so kinds that cannot
+/// be resolved, e. g. because there is no home directory, are skipped.
+///
+/// Returns `None` for relative paths and paths outside of all base directories.
+pub fn classify(path: &Path) -> Option<Classification> {
+    classify_with_projects(path, &[])
+}
+
+/// Like `classify`, but also considers the directories of the given projects,
+/// reporting the project name if `path` lies inside one of them.
+///
+/// Project directories are attributed to the corresponding base directory kind:
+/// `project_cache_dir` to `Cache`, `project_config_dir` to `Config`,
+/// `project_data_local_dir` to `Data`, `project_data_dir` to `DataRoaming` and
+/// `project_runtime_dir` to `Runtime`. Like for base directories, a project directory
+/// shared by several kinds is attributed to the first one in declaration order.
+pub fn classify_with_projects(path: &Path, projects: &[ProjectDirectories]) -> Option<Classification> {
+    DirectorySnapshot::new().classify(path, projects)
+}
+
+impl DirectorySnapshot {
+    /// Like `classify_with_projects`, but against the base directories of this snapshot.
+    pub fn classify(&self, path: &Path, projects: &[ProjectDirectories]) -> Option<Classification> {
+        let mut candidates: Vec<(DirectoryKind, &Path, Option<&str>)> = self
+            .iter()
+            .filter_map(|(kind, dir)| dir.map(|dir| (kind, dir, None)))
+            .collect();
+        for project in projects {
+            let name = Some(project.project_name());
+            candidates.push((DirectoryKind::Cache, project.project_cache_dir(), name));
+            candidates.push((DirectoryKind::Config, project.project_config_dir(), name));
+            candidates.push((DirectoryKind::Data, project.project_data_local_dir(), name));
+            candidates.push((DirectoryKind::DataRoaming, project.project_data_dir(), name));
+            if let Some(runtime_dir) = project.project_runtime_dir() {
+                candidates.push((DirectoryKind::Runtime, runtime_dir, name));
+            }
+        }
+        classify_among(path, &candidates)
+    }
+}
+
+fn classify_among(path: &Path, candidates: &[(DirectoryKind, &Path, Option<&str>)]) -> Option<Classification> {
+    let path = normalize(path)?;
+    let mut best: Option<(usize, Classification)> = None;
+    for &(kind, dir, project_name) in candidates {
+        let dir = match normalize(dir) {
+            Some(dir) => dir,
+            None => continue,
+        };
+        if !path.starts_with(&dir) {
+            continue;
+        }
+        let depth = dir.components().count();
+        let deeper = match best {
+            Some((best_depth, _)) => depth > best_depth,
+            None => true,
+        };
+        if deeper {
+            let project_name = project_name.map(String::from);
+            best = Some((depth, Classification { kind, project_name, dir }));
+        }
+    }
+    best.map(|(_, classification)| classification)
+}
+
+/// Normalizes an absolute path lexically, returns `None` for relative paths.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use classify::classify_among;
+    use kind::DirectoryKind;
+    use BaseDirectories;
+    use ProjectDirectories;
+
+    #[test]
+    fn test_classify_among() {
+        let candidates = [
+            (DirectoryKind::Home, Path::new("/home/eve"), None),
+            (DirectoryKind::Data, Path::new("/home/eve/.local/share"), None),
+            (DirectoryKind::DataRoaming, Path::new("/home/eve/.local/share"), None),
+            (DirectoryKind::Font, Path::new("/home/eve/.local/share/fonts"), None),
+            (DirectoryKind::Cache, Path::new("/home/eve/.cache"), None),
+            (DirectoryKind::Cache, Path::new("/home/eve/.cache/bar-app"), Some("bar-app")),
+        ];
+
+        let actual1 = classify_among(Path::new("/home/eve/.local/share/fonts/a.ttf"), &candidates).unwrap();
+        assert_eq!(actual1.kind(), DirectoryKind::Font);
+
+        let actual2 = classify_among(Path::new("/home/eve/.local/share/fonts/../x"), &candidates).unwrap();
+        assert_eq!(actual2.kind(), DirectoryKind::Data);
+
+        let actual3 = classify_among(Path::new("/home/eve/./.cache/bar-app/log"), &candidates).unwrap();
+        assert_eq!(actual3.kind(), DirectoryKind::Cache);
+        assert_eq!(actual3.project_name(), Some("bar-app"));
+        assert_eq!(actual3.dir(), Path::new("/home/eve/.cache/bar-app"));
+
+        let actual4 = classify_among(Path::new("/home/eve/.cachefoo"), &candidates).unwrap();
+        assert_eq!(actual4.kind(), DirectoryKind::Home);
+
+        assert_eq!(classify_among(Path::new("/etc/passwd"), &candidates), None);
+        assert_eq!(classify_among(Path::new("relative"), &candidates), None);
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_snapshot_classify() {
+        let dirs = BaseDirectories::from_env(vec![("HOME", "/home/eve")]);
+        let snapshot = dirs.snapshot();
+        let projects = [ProjectDirectories::from_project_name_with_base_dirs("Bar App", &dirs)];
+
+        let actual1 = snapshot.classify(Path::new("/home/eve/.local/share/bar-app/db"), &projects).unwrap();
+        assert_eq!(actual1.kind(), DirectoryKind::Data);
+        assert_eq!(actual1.project_name(), Some("bar-app"));
+
+        let actual2 = snapshot.classify(Path::new("/home/eve/.local/share/other/db"), &projects).unwrap();
+        assert_eq!(actual2.kind(), DirectoryKind::Data);
+        assert_eq!(actual2.project_name(), None);
+
+        let actual3 = snapshot.classify(Path::new("/home/eve/.config/bar-app/app.toml"), &projects).unwrap();
+        assert_eq!(actual3.kind(), DirectoryKind::Config);
+        assert_eq!(actual3.dir(), Path::new("/home/eve/.config/bar-app"));
+
+        let runtime = BaseDirectories::from_env(vec![("XDG_RUNTIME_DIR", "/run/user/1001")]).snapshot();
+        assert_eq!(runtime.classify(Path::new("/run/user/1001/sock"), &[]).unwrap().kind(), DirectoryKind::Runtime);
+    }
+}
@@ -0,0 +1,295 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use libc;
+
+use home::HomeDirError;
+use lin::env_var;
+use lin::xdg_base_dir;
+use passwd;
+use passwd::PasswdDatabase;
+use xdg::XDG_BASE_DIR_VARIABLES;
+
+/// The reasons a user-supplied path could not be expanded.
+#[derive(Debug)]
+pub enum ExpandError {
+    /// A `${` is not closed by a `}`, or a variable is followed by something other than `/`.
+    Syntax(String),
+    /// The variable is neither `HOME` nor one of the XDG base directory variables.
+    UnsupportedVariable(String),
+    /// The variable is unset and has no default, e. g. `XDG_RUNTIME_DIR`.
+    UndefinedVariable(String),
+    /// `~user` names a user without an entry in the password database.
+    UnknownUser(String),
+    /// The home directory of the current user could not be determined.
+    HomeDir(HomeDirError),
+    /// The password database could not be queried.
+    Io(io::Error),
+    /// The expanded path is not absolute.
+    NotAbsolute(PathBuf),
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExpandError::Syntax(ref input) => write!(f, "malformed variable reference in {:?}", input),
+            ExpandError::UnsupportedVariable(ref var) => write!(f, "unsupported variable ${}", var),
+            ExpandError::UndefinedVariable(ref var) => write!(f, "${} is not set", var),
+            ExpandError::UnknownUser(ref name) => write!(f, "no passwd entry for user {:?}", name),
+            ExpandError::HomeDir(ref err) => err.fmt(f),
+            ExpandError::Io(ref err) => write!(f, "cannot query the password database: {}", err),
+            ExpandError::NotAbsolute(ref path) => write!(f, "{:?} does not expand to an absolute path", path),
+        }
+    }
+}
+
+impl Error for ExpandError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ExpandError::HomeDir(ref err) => Some(err),
+            ExpandError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Expands a user-supplied path such as `~/notes`, `~bob/shared`,
+/// `$XDG_DATA_HOME/foo` or `${XDG_CACHE_HOME}/x` into an absolute path.
+///
+/// Only a leading `~`, `~user`, `$VAR` or `${VAR}` is expanded; a `$` or `~`
+/// anywhere else is taken literally. The supported variables are `HOME` and
+/// the XDG base directory variables, which fall back to the same defaults
+/// `BaseDirectories` uses if they are unset or not absolute.
+pub fn expand_path(input: &str) -> Result<PathBuf, ExpandError> {
+    let uid = unsafe { libc::geteuid() };
+    expand_path_with(input, &env_var, &PasswdDatabase::System, uid)
+}
+
+/// Like `expand_path`, but reads variables through `lookup` and user entries
+/// from `database`.
+pub fn expand_path_with<F: Fn(&str) -> Option<String>>(input: &str, lookup: &F, database: &PasswdDatabase, uid: u32) -> Result<PathBuf, ExpandError> {
+    let home_dir = || passwd::home_dir_from(lookup, database, uid).map_err(ExpandError::HomeDir);
+
+    let (base, rest) = if let Some(tilde) = input.strip_prefix('~') {
+        let (name, rest) = split_first_component(tilde);
+        let base = if name.is_empty() {
+            home_dir()?
+        } else {
+            match database.find_name(name) {
+                Ok(Some((entry, _))) => entry.home_dir().to_path_buf(),
+                Ok(None) => return Err(ExpandError::UnknownUser(String::from(name))),
+                Err(err) => return Err(ExpandError::Io(err)),
+            }
+        };
+        (Some(base), rest)
+    } else if let Some(dollar) = input.strip_prefix('$') {
+        let (var, rest) = if let Some(braced) = dollar.strip_prefix('{') {
+            let end = braced.find('}').ok_or_else(|| ExpandError::Syntax(String::from(input)))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = dollar
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(dollar.len());
+            (&dollar[..end], &dollar[end..])
+        };
+        if var.is_empty() || !(rest.is_empty() || rest.starts_with('/')) {
+            return Err(ExpandError::Syntax(String::from(input)));
+        }
+        let base = if var == "HOME" {
+            home_dir()?
+        } else if XDG_BASE_DIR_VARIABLES.contains(&var) {
+            let home_dir = home_dir()?;
+            xdg_base_dir(var, lookup, &home_dir).ok_or_else(|| ExpandError::UndefinedVariable(String::from(var)))?
+        } else {
+            return Err(ExpandError::UnsupportedVariable(String::from(var)));
+        };
+        (Some(base), rest)
+    } else {
+        (None, input)
+    };
+
+    let path = match base {
+        Some(base) => {
+            if !base.is_absolute() {
+                return Err(ExpandError::NotAbsolute(base));
+            }
+            base.join(rest.trim_start_matches('/'))
+        }
+        None => PathBuf::from(rest),
+    };
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        Err(ExpandError::NotAbsolute(path))
+    }
+}
+
+/// Contracts a path into its shortest symbolic form for display, the inverse of `expand_path`.
+///
+/// The path is expressed relative to the home directory (`~/…`) or one of the
+/// XDG base directories (`$XDG_CONFIG_HOME/…`), whichever yields the shortest
+/// result; paths outside of all of them are returned unchanged.
+pub fn contract_path(path: &Path) -> String {
+    let uid = unsafe { libc::geteuid() };
+    contract_path_with(path, &env_var, &PasswdDatabase::System, uid, false)
+}
+
+/// Like `contract_path`, but additionally replaces every remaining path component
+/// equal to the current user name with `<user>`, so the result can be shared in
+/// bug reports and diagnostics.
+pub fn contract_path_redacted(path: &Path) -> String {
+    let uid = unsafe { libc::geteuid() };
+    contract_path_with(path, &env_var, &PasswdDatabase::System, uid, true)
+}
+
+/// Like `contract_path` and `contract_path_redacted`, but reads variables through
+/// `lookup` and user entries from `database`.
+pub fn contract_path_with<F: Fn(&str) -> Option<String>>(path: &Path, lookup: &F, database: &PasswdDatabase, uid: u32, redact: bool) -> String {
+    let home_dir = passwd::home_dir_from(lookup, database, uid).ok();
+
+    let mut bases: Vec<(String, PathBuf)> = Vec::new();
+    if let Some(ref home_dir) = home_dir {
+        bases.push((String::from("~"), home_dir.clone()));
+    }
+    for var in XDG_BASE_DIR_VARIABLES.iter() {
+        let base = match home_dir {
+            Some(ref home_dir) => xdg_base_dir(var, lookup, home_dir),
+            None => lookup(var).map(PathBuf::from).filter(|dir| dir.is_absolute()),
+        };
+        if let Some(base) = base {
+            bases.push((format!("${}", var), base));
+        }
+    }
+
+    let mut best: Option<(String, usize)> = None;
+    for (symbol, base) in bases {
+        if let Ok(rest) = path.strip_prefix(&base) {
+            let mut components = vec![symbol];
+            components.extend(rest.components().map(|c| c.as_os_str().to_string_lossy().into_owned()));
+            let depth = base.components().count();
+            let candidate = components.join("/");
+            let is_better = match best {
+                Some((ref current, current_depth)) => {
+                    candidate.len() < current.len() || (candidate.len() == current.len() && depth > current_depth)
+                }
+                None => true,
+            };
+            if is_better {
+                best = Some((candidate, depth));
+            }
+        }
+    }
+    let contracted = match best {
+        Some((contracted, _)) => contracted,
+        None => path.to_string_lossy().into_owned(),
+    };
+    if !redact {
+        return contracted;
+    }
+
+    let user_name = database
+        .find_uid(uid)
+        .ok()
+        .and_then(|entry| entry.map(|(entry, _)| String::from(entry.name())))
+        .or_else(|| {
+            home_dir
+                .as_ref()
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+        });
+    match user_name {
+        Some(user_name) => contracted
+            .split('/')
+            .map(|component| if component == user_name { "<user>" } else { component })
+            .collect::<Vec<&str>>()
+            .join("/"),
+        None => contracted,
+    }
+}
+
+fn split_first_component(path: &str) -> (&str, &str) {
+    match path.find('/') {
+        Some(index) => (&path[..index], &path[index..]),
+        None => (path, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use expand::contract_path_with;
+    use expand::expand_path_with;
+    use expand::ExpandError;
+    use passwd::PasswdDatabase;
+
+    #[test]
+    fn test_expand_path_with() {
+        let passwd = env::temp_dir().join(format!("directories-expand-{}", ::std::process::id()));
+        fs::write(&passwd, "eve:x:1001:100::/home/eve:/bin/sh\nbob:x:1002:100::/home/bob:/bin/sh\n").unwrap();
+        let lookup = |key: &str| match key {
+            "XDG_CACHE_HOME" => Some(String::from("/var/tmp/cache")),
+            "XDG_CONFIG_HOME" => Some(String::from("relative")),
+            _ => None,
+        };
+        let expand = |input: &str| expand_path_with(input, &lookup, &PasswdDatabase::File(passwd.clone()), 1001);
+
+        assert_eq!(expand("~").unwrap(), PathBuf::from("/home/eve"));
+        assert_eq!(expand("~/notes").unwrap(), PathBuf::from("/home/eve/notes"));
+        assert_eq!(expand("~bob/shared").unwrap(), PathBuf::from("/home/bob/shared"));
+        assert_eq!(expand("$XDG_DATA_HOME/foo").unwrap(), PathBuf::from("/home/eve/.local/share/foo"));
+        assert_eq!(expand("${XDG_CACHE_HOME}/x").unwrap(), PathBuf::from("/var/tmp/cache/x"));
+        assert_eq!(expand("$XDG_CONFIG_HOME").unwrap(), PathBuf::from("/home/eve/.config"));
+        assert_eq!(expand("/srv/$XDG_DATA_HOME").unwrap(), PathBuf::from("/srv/$XDG_DATA_HOME"));
+
+        match expand("$XDG_RUNTIME_DIR/sock") {
+            Err(ExpandError::UndefinedVariable(ref var)) if var == "XDG_RUNTIME_DIR" => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match expand("$EDITOR") {
+            Err(ExpandError::UnsupportedVariable(ref var)) if var == "EDITOR" => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match expand("${XDG_CACHE_HOME}x") {
+            Err(ExpandError::Syntax(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match expand("~mallory/x") {
+            Err(ExpandError::UnknownUser(ref name)) if name == "mallory" => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match expand("notes") {
+            Err(ExpandError::NotAbsolute(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        fs::remove_file(&passwd).unwrap();
+    }
+
+    #[test]
+    fn test_contract_path_with() {
+        let passwd = env::temp_dir().join(format!("directories-contract-{}", ::std::process::id()));
+        fs::write(&passwd, "eve:x:1001:100::/home/eve:/bin/sh\n").unwrap();
+        let lookup = |key: &str| match key {
+            "XDG_CONFIG_HOME" => Some(String::from("/srv/config")),
+            "XDG_RUNTIME_DIR" => Some(String::from("/run/user/1001")),
+            _ => None,
+        };
+        let contract = |path: &str, redact: bool| contract_path_with(Path::new(path), &lookup, &PasswdDatabase::File(passwd.clone()), 1001, redact);
+
+        assert_eq!(contract("/home/eve/.cache/myapp/log", false), "~/.cache/myapp/log");
+        assert_eq!(contract("/srv/config/myapp/app.toml", false), "$XDG_CONFIG_HOME/myapp/app.toml");
+        assert_eq!(contract("/run/user/1001/myapp.sock", false), "$XDG_RUNTIME_DIR/myapp.sock");
+        assert_eq!(contract("/home/eve", false), "~");
+        assert_eq!(contract("/home/everyone/x", false), "/home/everyone/x");
+        assert_eq!(contract("/mnt/backup/eve/x", false), "/mnt/backup/eve/x");
+        assert_eq!(contract("/mnt/backup/eve/x", true), "/mnt/backup/<user>/x");
+
+        fs::remove_file(&passwd).unwrap();
+    }
+}
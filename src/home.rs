@@ -0,0 +1,44 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The reasons the home directory could not be determined.
+#[derive(Debug)]
+pub enum HomeDirError {
+    /// `$HOME` is unusable and the password database has no entry for the uid.
+    NoEntry(u32),
+    /// `$HOME` is unusable and the home directory of the password database entry is not absolute.
+    NotAbsolute(PathBuf),
+    /// `$HOME` is unusable and the password database could not be queried.
+    Io(io::Error),
+}
+
+impl fmt::Display for HomeDirError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HomeDirError::NoEntry(uid) => {
+                write!(f, "$HOME is unset or not absolute and there is no passwd entry for uid {}", uid)
+            }
+            HomeDirError::NotAbsolute(ref path) => write!(
+                f,
+                "$HOME is unset or not absolute and the passwd home directory {:?} is not absolute either",
+                path
+            ),
+            HomeDirError::Io(ref err) => write!(
+                f,
+                "$HOME is unset or not absolute and the password database cannot be queried: {}",
+                err
+            ),
+        }
+    }
+}
+
+impl Error for HomeDirError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            HomeDirError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
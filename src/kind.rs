@@ -0,0 +1,196 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use BaseDirectories;
+
+/// The kinds of directories provided by `BaseDirectories`.
+///
+/// The textual form used by `Display` and `FromStr` is the name of the
+/// corresponding `BaseDirectories` function without the `_dir` suffix,
+/// e. g. `data_roaming` for `BaseDirectories::data_roaming_dir`.
+///
+/// More kinds may be added in future versions, so matches have to include a
+/// wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum DirectoryKind {
+    /// See `BaseDirectories::home_dir`.
+    Home,
+    /// See `BaseDirectories::cache_dir`.
+    Cache,
+    /// See `BaseDirectories::config_dir`.
+    Config,
+    /// See `BaseDirectories::data_dir`.
+    Data,
+    /// See `BaseDirectories::data_roaming_dir`.
+    DataRoaming,
+    /// See `BaseDirectories::executable_dir`.
+    Executable,
+    /// See `BaseDirectories::runtime_dir`.
+    Runtime,
+    /// See `BaseDirectories::audio_dir`.
+    Audio,
+    /// See `BaseDirectories::desktop_dir`.
+    Desktop,
+    /// See `BaseDirectories::document_dir`.
+    Document,
+    /// See `BaseDirectories::download_dir`.
+    Download,
+    /// See `BaseDirectories::font_dir`.
+    Font,
+    /// See `BaseDirectories::picture_dir`.
+    Picture,
+    /// See `BaseDirectories::public_dir`.
+    Public,
+    /// See `BaseDirectories::template_dir`.
+    Template,
+    /// See `BaseDirectories::video_dir`.
+    Video,
+    /// See `BaseDirectories::preference_dir`.
+    Preference,
+    /// See `BaseDirectories::data_low_dir`.
+    DataLow,
+    /// See `BaseDirectories::program_data_dir`.
+    ProgramData,
+    /// See `BaseDirectories::user_program_dir`.
+    UserProgram,
+    /// See `BaseDirectories::system_font_dir`.
+    SystemFont,
+}
+
+/// The error returned by `DirectoryKind::from_str` for unknown names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirectoryKindError(String);
+
+const ALL_KINDS: [DirectoryKind; 21] = [
+    DirectoryKind::Home,
+    DirectoryKind::Cache,
+    DirectoryKind::Config,
+    DirectoryKind::Data,
+    DirectoryKind::DataRoaming,
+    DirectoryKind::Executable,
+    DirectoryKind::Runtime,
+    DirectoryKind::Audio,
+    DirectoryKind::Desktop,
+    DirectoryKind::Document,
+    DirectoryKind::Download,
+    DirectoryKind::Font,
+    DirectoryKind::Picture,
+    DirectoryKind::Public,
+    DirectoryKind::Template,
+    DirectoryKind::Video,
+    DirectoryKind::Preference,
+    DirectoryKind::DataLow,
+    DirectoryKind::ProgramData,
+    DirectoryKind::UserProgram,
+    DirectoryKind::SystemFont,
+];
+
+#[deny(missing_docs)]
+impl DirectoryKind {
+    /// Returns an iterator over all kinds, in declaration order.
+    pub fn all() -> impl Iterator<Item = DirectoryKind> {
+        ALL_KINDS.iter().cloned()
+    }
+
+    /// Returns the textual form of the kind, e. g. `data_roaming`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DirectoryKind::Home => "home",
+            DirectoryKind::Cache => "cache",
+            DirectoryKind::Config => "config",
+            DirectoryKind::Data => "data",
+            DirectoryKind::DataRoaming => "data_roaming",
+            DirectoryKind::Executable => "executable",
+            DirectoryKind::Runtime => "runtime",
+            DirectoryKind::Audio => "audio",
+            DirectoryKind::Desktop => "desktop",
+            DirectoryKind::Document => "document",
+            DirectoryKind::Download => "download",
+            DirectoryKind::Font => "font",
+            DirectoryKind::Picture => "picture",
+            DirectoryKind::Public => "public",
+            DirectoryKind::Template => "template",
+            DirectoryKind::Video => "video",
+            DirectoryKind::Preference => "preference",
+            DirectoryKind::DataLow => "data_low",
+            DirectoryKind::ProgramData => "program_data",
+            DirectoryKind::UserProgram => "user_program",
+            DirectoryKind::SystemFont => "system_font",
+        }
+    }
+}
+
+impl fmt::Display for DirectoryKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DirectoryKind {
+    type Err = ParseDirectoryKindError;
+
+    fn from_str(s: &str) -> Result<DirectoryKind, ParseDirectoryKindError> {
+        DirectoryKind::all()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| ParseDirectoryKindError(String::from(s)))
+    }
+}
+
+impl fmt::Display for ParseDirectoryKindError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown directory kind {:?}", self.0)
+    }
+}
+
+impl Error for ParseDirectoryKindError {}
+
+impl BaseDirectories {
+    /// Returns the path to the directory of the given kind, or `None` if the
+    /// platform does not provide one.
+    ///
+    /// This is equivalent to calling the method named after the kind, e. g.
+    /// `BaseDirectories::get(DirectoryKind::Font)` returns `self.font()`.
+    pub fn get(&self, kind: DirectoryKind) -> Option<PathBuf> {
+        match kind {
+            DirectoryKind::Home => Some(self.home()),
+            DirectoryKind::Cache => Some(self.cache()),
+            DirectoryKind::Config => Some(self.config()),
+            DirectoryKind::Data => Some(self.data()),
+            DirectoryKind::DataRoaming => Some(self.data_roaming()),
+            DirectoryKind::Executable => self.executable(),
+            DirectoryKind::Runtime => self.runtime(),
+            DirectoryKind::Audio => Some(self.audio()),
+            DirectoryKind::Desktop => Some(self.desktop()),
+            DirectoryKind::Document => Some(self.document()),
+            DirectoryKind::Download => Some(self.download()),
+            DirectoryKind::Font => self.font(),
+            DirectoryKind::Picture => Some(self.picture()),
+            DirectoryKind::Public => Some(self.public()),
+            DirectoryKind::Template => self.template(),
+            DirectoryKind::Video => Some(self.video()),
+            DirectoryKind::Preference => self.preference(),
+            DirectoryKind::DataLow => self.data_low(),
+            DirectoryKind::ProgramData => self.program_data(),
+            DirectoryKind::UserProgram => self.user_program(),
+            DirectoryKind::SystemFont => self.system_font(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kind::DirectoryKind;
+
+    #[test]
+    fn test_directory_kind_round_trip() {
+        assert_eq!(DirectoryKind::all().count(), 21);
+        for kind in DirectoryKind::all() {
+            assert_eq!(kind.to_string().parse(), Ok(kind));
+        }
+        assert_eq!("data_roaming".parse(), Ok(DirectoryKind::DataRoaming));
+        assert!("data_roaming_dir".parse::<DirectoryKind>().is_err());
+    }
+}
@@ -0,0 +1,688 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use kind::DirectoryKind;
+use policy::ConfigDirPolicy;
+use provenance::Explanation;
+use BaseDirBackend;
+use BaseDirectories;
+use strip_qualification;
+use trim_and_replace_spaces_with_hyphens_then_lowercase;
+use ProjectDirectories;
+use xdg::explain_with_home;
+
+/// The platforms whose directory layout can be computed on any host.
+///
+/// Unlike `BaseDirectories`, which asks the operating system the library runs
+/// on, a `Platform` derives all directories from the values in `PlatformFolders`
+/// without touching the environment or the file system. This makes it possible
+/// to compute e. g. the paths of a Windows installation while running on Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    /// The XDG layout used on Linux and other Unix platforms except macOS.
+    Linux,
+    /// The layout of the Standard Directories on macOS.
+    MacOs,
+    /// The layout of the Known Folders on Windows.
+    Windows,
+}
+
+/// A `BaseDirBackend` returning the layout of a `Platform` for fixed `PlatformFolders`.
+///
+/// This allows all functionality built on `BaseDirectories`, e. g. `explain` or
+/// `snapshot`, to be used with simulated inputs. The environment of the
+/// `BaseDirectories` value is not consulted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformBackend {
+    platform: Platform,
+    folders: PlatformFolders,
+}
+
+/// The Windows known folders a layout is computed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownFolder {
+    /// `{FOLDERID_Profile}`, e. g. `C:\Users\Eve`.
+    Profile,
+    /// `{FOLDERID_RoamingAppData}`, by default `{FOLDERID_Profile}\AppData\Roaming`.
+    RoamingAppData,
+    /// `{FOLDERID_LocalAppData}`, by default `{FOLDERID_Profile}\AppData\Local`.
+    LocalAppData,
+    /// `{FOLDERID_Desktop}`, by default `{FOLDERID_Profile}\Desktop`.
+    Desktop,
+    /// `{FOLDERID_Documents}`, by default `{FOLDERID_Profile}\Documents`.
+    Documents,
+    /// `{FOLDERID_Downloads}`, by default `{FOLDERID_Profile}\Downloads`.
+    Downloads,
+    /// `{FOLDERID_Music}`, by default `{FOLDERID_Profile}\Music`.
+    Music,
+    /// `{FOLDERID_Pictures}`, by default `{FOLDERID_Profile}\Pictures`.
+    Pictures,
+    /// `{FOLDERID_Public}`, by default `Public` next to `{FOLDERID_Profile}`, e. g. `C:\Users\Public`.
+    Public,
+    /// `{FOLDERID_Templates}`, by default `{FOLDERID_RoamingAppData}\Microsoft\Windows\Templates`.
+    Templates,
+    /// `{FOLDERID_Videos}`, by default `{FOLDERID_Profile}\Videos`.
+    Videos,
+    /// `{FOLDERID_LocalAppDataLow}`, by default `{FOLDERID_Profile}\AppData\LocalLow`.
+    LocalAppDataLow,
+    /// `{FOLDERID_ProgramData}`, by default `ProgramData` on the drive of `{FOLDERID_Profile}`, e. g. `C:\ProgramData`.
+    ProgramData,
+    /// `{FOLDERID_Fonts}`, by default `Windows\Fonts` on the drive of `{FOLDERID_Profile}`, e. g. `C:\Windows\Fonts`.
+    Fonts,
+    /// `{FOLDERID_UserProgramFiles}`, by default `{FOLDERID_LocalAppData}\Programs`.
+    UserProgramFiles,
+}
+
+/// What the default location of a `KnownFolder` is relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    /// Another known folder.
+    Folder(KnownFolder),
+    /// The directory containing `{FOLDERID_Profile}`, e. g. `C:\Users`.
+    Users,
+    /// The root of the drive containing `{FOLDERID_Profile}`, e. g. `C:\`.
+    Drive,
+}
+
+/// The inputs a `Platform` computes its directories from.
+///
+/// Only the home directory is required. On Linux, XDG variables such as
+/// `XDG_CONFIG_HOME` or `XDG_MUSIC_DIR` override their defaults; on Windows,
+/// known folders missing from the map are derived from `{FOLDERID_Profile}`,
+/// which is the home directory unless set explicitly. On macOS, the config
+/// directory depends on the `ConfigDirPolicy`, by default `Preferences`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformFolders {
+    home_dir: PathBuf,
+    vars: HashMap<String, String>,
+    known_folders: HashMap<KnownFolder, PathBuf>,
+    config_dir_policy: ConfigDirPolicy,
+}
+
+#[deny(missing_docs)]
+impl PlatformFolders {
+    /// Creates the inputs for a user with the given home directory.
+    pub fn new<P: Into<PathBuf>>(home_dir: P) -> PlatformFolders {
+        PlatformFolders {
+            home_dir: home_dir.into(),
+            vars: HashMap::new(),
+            known_folders: HashMap::new(),
+            config_dir_policy: ConfigDirPolicy::Preferences,
+        }
+    }
+
+    /// Sets an XDG variable, e. g. `XDG_CACHE_HOME`, considered by `Platform::Linux`.
+    pub fn with_var(mut self, key: &str, value: &str) -> PlatformFolders {
+        self.vars.insert(String::from(key), String::from(value));
+        self
+    }
+
+    /// Sets the location of a known folder, considered by `Platform::Windows`.
+    pub fn with_known_folder<P: Into<PathBuf>>(mut self, folder: KnownFolder, path: P) -> PlatformFolders {
+        self.known_folders.insert(folder, path.into());
+        self
+    }
+
+    /// Sets where configuration is kept, considered by `Platform::MacOs`.
+    pub fn with_config_dir_policy(mut self, policy: ConfigDirPolicy) -> PlatformFolders {
+        self.config_dir_policy = policy;
+        self
+    }
+
+    /// Returns where configuration is kept on macOS.
+    pub fn config_dir_policy(&self) -> ConfigDirPolicy {
+        self.config_dir_policy
+    }
+
+    /// Returns the home directory.
+    pub fn home_dir(&self) -> &Path {
+        self.home_dir.as_path()
+    }
+
+    /// Returns the location of a known folder, falling back to its default below `{FOLDERID_Profile}`.
+    pub fn known_folder(&self, folder: KnownFolder) -> PathBuf {
+        if let Some(path) = self.known_folders.get(&folder) {
+            return path.clone();
+        }
+        let windows = Platform::Windows;
+        let (anchor, relative) = match folder.default_location() {
+            Some(location) => location,
+            None => return self.home_dir.clone(),
+        };
+        let base = match anchor {
+            Anchor::Folder(base) => self.known_folder(base),
+            Anchor::Users => windows.parent(&self.known_folder(KnownFolder::Profile)),
+            Anchor::Drive => windows.drive(&self.known_folder(KnownFolder::Profile)),
+        };
+        windows.join(&base, relative)
+    }
+}
+
+impl KnownFolder {
+    /// Returns the known folder the Windows directory of the given kind is located in,
+    /// or `None` if Windows does not provide one.
+    fn of_kind(kind: DirectoryKind) -> Option<KnownFolder> {
+        match kind {
+            DirectoryKind::Home => Some(KnownFolder::Profile),
+            DirectoryKind::Config | DirectoryKind::DataRoaming => Some(KnownFolder::RoamingAppData),
+            DirectoryKind::Cache | DirectoryKind::Data => Some(KnownFolder::LocalAppData),
+            DirectoryKind::DataLow => Some(KnownFolder::LocalAppDataLow),
+            DirectoryKind::ProgramData => Some(KnownFolder::ProgramData),
+            DirectoryKind::UserProgram => Some(KnownFolder::UserProgramFiles),
+            DirectoryKind::Executable | DirectoryKind::Runtime | DirectoryKind::Preference => None,
+            DirectoryKind::Font => None,
+            DirectoryKind::SystemFont => Some(KnownFolder::Fonts),
+            DirectoryKind::Audio => Some(KnownFolder::Music),
+            DirectoryKind::Desktop => Some(KnownFolder::Desktop),
+            DirectoryKind::Document => Some(KnownFolder::Documents),
+            DirectoryKind::Download => Some(KnownFolder::Downloads),
+            DirectoryKind::Picture => Some(KnownFolder::Pictures),
+            DirectoryKind::Public => Some(KnownFolder::Public),
+            DirectoryKind::Template => Some(KnownFolder::Templates),
+            DirectoryKind::Video => Some(KnownFolder::Videos),
+        }
+    }
+
+    /// Returns the default location as an anchor and a path relative to it,
+    /// or `None` for `Profile`, which defaults to the home directory.
+    fn default_location(self) -> Option<(Anchor, &'static str)> {
+        let profile = Anchor::Folder(KnownFolder::Profile);
+        match self {
+            KnownFolder::Profile => None,
+            KnownFolder::RoamingAppData => Some((profile, "AppData/Roaming")),
+            KnownFolder::LocalAppData => Some((profile, "AppData/Local")),
+            KnownFolder::LocalAppDataLow => Some((profile, "AppData/LocalLow")),
+            KnownFolder::Desktop => Some((profile, "Desktop")),
+            KnownFolder::Documents => Some((profile, "Documents")),
+            KnownFolder::Downloads => Some((profile, "Downloads")),
+            KnownFolder::Music => Some((profile, "Music")),
+            KnownFolder::Pictures => Some((profile, "Pictures")),
+            KnownFolder::Videos => Some((profile, "Videos")),
+            KnownFolder::Templates => Some((Anchor::Folder(KnownFolder::RoamingAppData), "Microsoft/Windows/Templates")),
+            KnownFolder::UserProgramFiles => Some((Anchor::Folder(KnownFolder::LocalAppData), "Programs")),
+            KnownFolder::Public => Some((Anchor::Users, "Public")),
+            KnownFolder::ProgramData => Some((Anchor::Drive, "ProgramData")),
+            KnownFolder::Fonts => Some((Anchor::Drive, "Windows/Fonts")),
+        }
+    }
+}
+
+#[deny(missing_docs)]
+impl PlatformBackend {
+    /// Creates a backend computing the layout of `platform` from `folders`.
+    pub fn new(platform: Platform, folders: PlatformFolders) -> PlatformBackend {
+        PlatformBackend { platform, folders }
+    }
+
+    /// Returns the simulated platform.
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    /// Returns the inputs the directories are computed from.
+    pub fn folders(&self) -> &PlatformFolders {
+        &self.folders
+    }
+}
+
+impl BaseDirBackend for PlatformBackend {
+    fn home_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Home, &self.folders).unwrap()
+    }
+
+    fn cache_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Cache, &self.folders).unwrap()
+    }
+
+    fn config_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Config, &self.folders).unwrap()
+    }
+
+    fn preference_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::Preference, &self.folders)
+    }
+
+    fn data_roaming_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::DataRoaming, &self.folders).unwrap()
+    }
+
+    fn data_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Data, &self.folders).unwrap()
+    }
+
+    fn data_low_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::DataLow, &self.folders)
+    }
+
+    fn program_data_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::ProgramData, &self.folders)
+    }
+
+    fn executable_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::Executable, &self.folders)
+    }
+
+    fn user_program_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::UserProgram, &self.folders)
+    }
+
+    fn system_font_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::SystemFont, &self.folders)
+    }
+
+    fn runtime_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::Runtime, &self.folders)
+    }
+
+    fn audio_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Audio, &self.folders).unwrap()
+    }
+
+    fn desktop_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Desktop, &self.folders).unwrap()
+    }
+
+    fn document_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Document, &self.folders).unwrap()
+    }
+
+    fn download_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Download, &self.folders).unwrap()
+    }
+
+    fn font_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::Font, &self.folders)
+    }
+
+    fn picture_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Picture, &self.folders).unwrap()
+    }
+
+    fn public_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Public, &self.folders).unwrap()
+    }
+
+    fn template_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        self.platform.base_dir(DirectoryKind::Template, &self.folders)
+    }
+
+    fn video_dir(&self, _: &BaseDirectories) -> PathBuf {
+        self.platform.base_dir(DirectoryKind::Video, &self.folders).unwrap()
+    }
+}
+
+#[deny(missing_docs)]
+impl Platform {
+    /// Returns the platform the library has been compiled for.
+    pub fn current() -> Platform {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+
+    /// Returns the directory of the given kind on this platform, or `None` if the platform has none.
+    ///
+    /// The values correspond to those of `BaseDirectories` on the respective platform.
+    pub fn base_dir(self, kind: DirectoryKind, folders: &PlatformFolders) -> Option<PathBuf> {
+        let home = folders.home_dir();
+        match self {
+            Platform::Linux => {
+                let lookup = |key: &str| folders.vars.get(key).cloned();
+                let home = Explanation::new(DirectoryKind::Home, Some(home.to_path_buf()), None, Vec::new());
+                explain_with_home(kind, &lookup, &|_: &Path| None, &home).into_path()
+            }
+            Platform::MacOs => match kind {
+                DirectoryKind::Home => Some(home.to_path_buf()),
+                DirectoryKind::Cache => Some(home.join("Library/Caches")),
+                DirectoryKind::Config => match folders.config_dir_policy() {
+                    ConfigDirPolicy::Preferences => Some(home.join("Library/Preferences")),
+                    ConfigDirPolicy::ApplicationSupport => Some(home.join("Library/Application Support")),
+                },
+                DirectoryKind::Preference => Some(home.join("Library/Preferences")),
+                DirectoryKind::Data | DirectoryKind::DataRoaming => Some(home.join("Library/Application Support")),
+                DirectoryKind::Executable | DirectoryKind::Runtime | DirectoryKind::Template => None,
+                DirectoryKind::DataLow | DirectoryKind::ProgramData | DirectoryKind::UserProgram => None,
+                DirectoryKind::SystemFont => None,
+                DirectoryKind::Font => Some(home.join("Library/Fonts")),
+                DirectoryKind::Audio => Some(home.join("Music")),
+                DirectoryKind::Desktop => Some(home.join("Desktop")),
+                DirectoryKind::Document => Some(home.join("Documents")),
+                DirectoryKind::Download => Some(home.join("Downloads")),
+                DirectoryKind::Picture => Some(home.join("Pictures")),
+                DirectoryKind::Public => Some(home.join("Public")),
+                DirectoryKind::Video => Some(home.join("Movies")),
+            },
+            Platform::Windows => {
+                let path = folders.known_folder(KnownFolder::of_kind(kind)?);
+                match kind {
+                    DirectoryKind::Cache => Some(self.join(&path, "cache")),
+                    _ => Some(path),
+                }
+            }
+        }
+    }
+
+    /// Computes the project directories this platform would produce for
+    /// `ProjectDirectories::from_unprocessed_string(value)`.
+    pub fn project_dirs_from_unprocessed_string(self, value: &str, folders: &PlatformFolders) -> ProjectDirectories {
+        let base_dir = |kind| self.base_dir(kind, folders).map(|dir| self.join(&dir, value));
+        let (project_cache_dir, project_data_local_dir) = match self {
+            Platform::Windows => {
+                let data_local_dir = self.join(&folders.known_folder(KnownFolder::LocalAppData), value);
+                (self.join(&data_local_dir, "cache"), data_local_dir)
+            }
+            _ => (base_dir(DirectoryKind::Cache).unwrap(), base_dir(DirectoryKind::Data).unwrap()),
+        };
+        ProjectDirectories {
+            project_name: String::from(value),
+            project_cache_dir,
+            project_config_dir: base_dir(DirectoryKind::Config).unwrap(),
+            project_data_dir: base_dir(DirectoryKind::DataRoaming).unwrap(),
+            project_data_local_dir,
+            project_runtime_dir: base_dir(DirectoryKind::Runtime),
+            service_dirs: None,
+        }
+    }
+
+    /// Computes the project directories this platform would produce for
+    /// `ProjectDirectories::from_project_name(project_name)`.
+    pub fn project_dirs_from_project_name(self, project_name: &str, folders: &PlatformFolders) -> ProjectDirectories {
+        self.project_dirs_from_unprocessed_string(&project_dir_name(self, project_name), folders)
+    }
+
+    /// Computes the project directories this platform would produce for
+    /// `ProjectDirectories::from_qualified_project_name(qualified_project_name)`.
+    pub fn project_dirs_from_qualified_project_name(self, qualified_project_name: &str, folders: &PlatformFolders) -> ProjectDirectories {
+        let name = qualified_project_dir_name(self, qualified_project_name);
+        self.project_dirs_from_unprocessed_string(&name, folders)
+    }
+
+    /// Computes the project directories this platform would produce for
+    /// `ProjectDirectories::from_qualified_project_name_nested(qualified_project_name)`.
+    ///
+    /// On Windows, the organization and the application each get a directory,
+    /// e. g. `com.FooCorp.Bar App` yields `{FOLDERID_RoamingAppData}\FooCorp\Bar App`.
+    /// Characters Windows does not allow in these directory names are escaped,
+    /// see `escape_path_component`.
+    /// All other platforms produce the same directories as `project_dirs_from_qualified_project_name`.
+    pub fn project_dirs_from_qualified_project_name_nested(self, qualified_project_name: &str, folders: &PlatformFolders) -> ProjectDirectories {
+        match self {
+            Platform::Windows => {
+                let (organization, application) = split_qualified_name(qualified_project_name);
+                let escaped = escape_path_component(application);
+                let mut project_dirs = match organization {
+                    Some(organization) => {
+                        let nested = format!("{}\\{}", escape_path_component(&organization), escaped);
+                        self.project_dirs_from_unprocessed_string(&nested, folders)
+                    }
+                    None => self.project_dirs_from_unprocessed_string(&escaped, folders),
+                };
+                project_dirs.project_name = String::from(application);
+                project_dirs
+            }
+            _ => self.project_dirs_from_qualified_project_name(qualified_project_name, folders),
+        }
+    }
+
+    /// Appends `relative` to `base`, using the path separator of this platform
+    /// regardless of the host, so that e. g. Windows paths contain only `\`.
+    fn join(self, base: &Path, relative: &str) -> PathBuf {
+        if self != Platform::Windows {
+            return if relative.is_empty() { base.to_path_buf() } else { base.join(relative) };
+        }
+        let mut path = base.to_string_lossy().into_owned();
+        for component in relative.split(['/', '\\']).filter(|c| !c.is_empty()) {
+            if !path.ends_with('\\') {
+                path.push('\\');
+            }
+            path.push_str(component);
+        }
+        PathBuf::from(path)
+    }
+
+    /// Returns the drive of a Windows path, e. g. `C:` for `C:\Users\Eve`,
+    /// falling back to `C:` for paths without a drive letter.
+    fn drive(self, path: &Path) -> PathBuf {
+        let path = path.to_string_lossy();
+        match path.split('\\').next() {
+            Some(drive) if drive.len() == 2 && drive.ends_with(':') => PathBuf::from(drive),
+            _ => PathBuf::from("C:"),
+        }
+    }
+
+    /// Returns the parent of `path`, using the path separator of this platform.
+    fn parent(self, path: &Path) -> PathBuf {
+        let path = path.to_string_lossy();
+        let separator = if self == Platform::Windows { '\\' } else { '/' };
+        match path.trim_end_matches(separator).rfind(separator) {
+            Some(0) => PathBuf::from(separator.to_string()),
+            Some(index) => PathBuf::from(&path[..index]),
+            None => PathBuf::from(path.as_ref()),
+        }
+    }
+}
+
+/// Returns the directory name `ProjectDirectories::from_project_name` uses on the given platform.
+pub fn project_dir_name(platform: Platform, project_name: &str) -> String {
+    match platform {
+        Platform::Linux => trim_and_replace_spaces_with_hyphens_then_lowercase(project_name),
+        _ => String::from(project_name),
+    }
+}
+
+/// Returns the directory name `ProjectDirectories::from_qualified_project_name` uses on the given platform.
+pub fn qualified_project_dir_name(platform: Platform, qualified_project_name: &str) -> String {
+    match platform {
+        Platform::Linux => String::from(strip_qualification(qualified_project_name).to_lowercase().trim()),
+        Platform::MacOs => String::from(qualified_project_name),
+        Platform::Windows => String::from(strip_qualification(qualified_project_name).trim()),
+    }
+}
+
+/// Splits a qualified name like `com.FooCorp.BarApp` into the organization
+/// `FooCorp` and the application `BarApp`.
+///
+/// The first component is the top-level domain and is dropped; any components
+/// between the organization and the application stay part of the organization,
+/// joined by `.`, so `com.Foo.Tools.App` and `com.Foo.ToolsApp` remain distinct.
+/// Components are kept as they are, including empty ones and surrounding spaces,
+/// so that distinct names never share a directory; see `escape_path_component`.
+fn split_qualified_name(qualified_project_name: &str) -> (Option<String>, &str) {
+    let mut components: Vec<&str> = qualified_project_name.split('.').collect();
+    let application = components.pop().unwrap_or("");
+    if components.len() < 2 {
+        return (None, application);
+    }
+    (Some(components[1..].join(".")), application)
+}
+
+/// Escapes a Windows directory name, so that every name maps to a distinct directory.
+///
+/// `%`, the characters Windows forbids (`<>:"/\|?*` and control characters),
+/// leading and trailing spaces and trailing dots, which Windows would drop,
+/// are replaced by `%` followed by the hexadecimal value of each of their bytes,
+/// e. g. `A/B` becomes `A%2FB`. The empty name becomes `%`.
+fn escape_path_component(component: &str) -> String {
+    if component.is_empty() {
+        return String::from("%");
+    }
+    let start = component.len() - component.trim_start_matches(' ').len();
+    let end = component.trim_end_matches([' ', '.']).len();
+    let mut escaped = String::with_capacity(component.len());
+    for (index, c) in component.char_indices() {
+        if index < start || index >= end || c.is_control() || "%<>:\"/\\|?*".contains(c) {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Computes the inputs of the Windows layout for the directories of the given kinds,
+/// asking `query` only for the known folders these are located in.
+///
+/// Folders `query` cannot resolve are left to their defaults, and the folders
+/// these defaults are based on are queried in turn. The home directory is only
+/// set if `{FOLDERID_Profile}` is needed; this panics if it cannot be resolved.
+#[cfg(any(target_os = "windows", test))]
+pub fn windows_folders<Q: Fn(KnownFolder) -> Option<PathBuf>>(kinds: &[DirectoryKind], query: Q) -> PlatformFolders {
+    let mut folders = PlatformFolders::new(PathBuf::new());
+    let mut pending: Vec<KnownFolder> = kinds.iter().filter_map(|&kind| KnownFolder::of_kind(kind)).collect();
+    let mut queried = Vec::new();
+    while let Some(folder) = pending.pop() {
+        if queried.contains(&folder) {
+            continue;
+        }
+        queried.push(folder);
+        match (query(folder), folder.default_location()) {
+            (Some(path), None) => folders.home_dir = path,
+            (Some(path), Some(_)) => folders = folders.with_known_folder(folder, path),
+            (None, Some((Anchor::Folder(base), _))) => pending.push(base),
+            (None, Some(_)) => pending.push(KnownFolder::Profile),
+            (None, None) => panic!("the known folder FOLDERID_Profile cannot be resolved"),
+        }
+    }
+    folders
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use kind::DirectoryKind;
+    use layout::windows_folders;
+    use layout::KnownFolder;
+    use layout::Platform;
+    use layout::PlatformBackend;
+    use layout::PlatformFolders;
+    use BaseDirectories;
+
+    #[test]
+    fn test_platform_project_dirs() {
+        let windows = PlatformFolders::new(r"C:\Users\Eve")
+            .with_known_folder(KnownFolder::LocalAppData, r"D:\Local");
+        let actual1 = Platform::Windows.project_dirs_from_qualified_project_name("org.foo.Bar App", &windows);
+        assert_eq!(actual1.project_cache_dir(), Path::new(r"D:\Local\Bar App\cache"));
+        assert_eq!(actual1.project_config_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\Bar App"));
+        assert_eq!(actual1.project_data_local_dir(), Path::new(r"D:\Local\Bar App"));
+        assert_eq!(actual1.project_runtime_dir(), None);
+        let actual2 = Platform::Windows.base_dir(DirectoryKind::Public, &windows);
+        assert_eq!(actual2, Some(PathBuf::from(r"C:\Users\Public")));
+
+        let mac = PlatformFolders::new("/Users/eve");
+        let actual3 = Platform::MacOs.project_dirs_from_project_name("Bar App", &mac);
+        assert_eq!(actual3.project_config_dir(), Path::new("/Users/eve/Library/Preferences/Bar App"));
+        assert_eq!(actual3.project_data_dir(), Path::new("/Users/eve/Library/Application Support/Bar App"));
+
+        let linux = PlatformFolders::new("/home/eve")
+            .with_var("XDG_CACHE_HOME", "/var/cache/eve")
+            .with_var("XDG_CONFIG_HOME", "relative")
+            .with_var("XDG_RUNTIME_DIR", "/run/user/1001")
+            .with_var("XDG_MUSIC_DIR", "/srv/music");
+        assert_eq!(Platform::Linux.base_dir(DirectoryKind::Audio, &linux), Some(PathBuf::from("/srv/music")));
+        assert_eq!(Platform::Linux.base_dir(DirectoryKind::Desktop, &linux), Some(PathBuf::from("/home/eve/Desktop")));
+        let actual4 = Platform::Linux.project_dirs_from_project_name("Bar App", &linux);
+        assert_eq!(actual4.project_name(), "bar-app");
+        assert_eq!(actual4.project_cache_dir(), Path::new("/var/cache/eve/bar-app"));
+        assert_eq!(actual4.project_config_dir(), Path::new("/home/eve/.config/bar-app"));
+        assert_eq!(actual4.project_runtime_dir(), Some(Path::new("/run/user/1001/bar-app")));
+    }
+
+    #[test]
+    fn test_nested_project_dirs() {
+        let windows = PlatformFolders::new(r"C:\Users\Eve");
+        let nested = |name| Platform::Windows.project_dirs_from_qualified_project_name_nested(name, &windows);
+        let actual1 = nested("com.FooCorp.Bar App");
+        assert_eq!(actual1.project_name(), "Bar App");
+        assert_eq!(actual1.project_config_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\FooCorp\Bar App"));
+        assert_eq!(actual1.project_cache_dir(), Path::new(r"C:\Users\Eve\AppData\Local\FooCorp\Bar App\cache"));
+        let actual2 = nested("com.Foo Corp.Tools.Bar");
+        assert_eq!(actual2.project_data_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\Foo Corp.Tools\Bar"));
+        let actual3 = nested(" com . FooCorp .. Bar ");
+        assert_eq!(actual3.project_config_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\%20FooCorp%20%2E\%20Bar%20"));
+        let actual5 = nested("com.Foo.A/B:C");
+        assert_eq!(actual5.project_name(), "A/B:C");
+        assert_eq!(actual5.project_config_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\Foo\A%2FB%3AC"));
+        assert_eq!(nested("com.Foo..Bar").project_config_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\Foo%2E\Bar"));
+        assert_eq!(nested("com..Bar").project_config_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\%\Bar"));
+        assert_eq!(nested("com.Bar").project_config_dir(), Path::new(r"C:\Users\Eve\AppData\Roaming\Bar"));
+
+        let linux = PlatformFolders::new("/home/eve");
+        let actual4 = Platform::Linux.project_dirs_from_qualified_project_name_nested("com.FooCorp.Bar", &linux);
+        assert_eq!(actual4.project_config_dir(), Path::new("/home/eve/.config/bar"));
+    }
+
+    #[test]
+    fn test_windows_known_folder_defaults() {
+        let windows = PlatformFolders::new(r"D:\Users\Eve")
+            .with_known_folder(KnownFolder::LocalAppData, r"E:\Local");
+        let base_dir = |kind| Platform::Windows.base_dir(kind, &windows);
+        assert_eq!(base_dir(DirectoryKind::DataLow), Some(PathBuf::from(r"D:\Users\Eve\AppData\LocalLow")));
+        assert_eq!(base_dir(DirectoryKind::ProgramData), Some(PathBuf::from(r"D:\ProgramData")));
+        assert_eq!(base_dir(DirectoryKind::Font), None);
+        assert_eq!(base_dir(DirectoryKind::SystemFont), Some(PathBuf::from(r"D:\Windows\Fonts")));
+        assert_eq!(base_dir(DirectoryKind::UserProgram), Some(PathBuf::from(r"E:\Local\Programs")));
+
+        let redirected = windows.with_known_folder(KnownFolder::ProgramData, r"F:\Shared");
+        let actual = Platform::Windows.base_dir(DirectoryKind::ProgramData, &redirected);
+        assert_eq!(actual, Some(PathBuf::from(r"F:\Shared")));
+        assert_eq!(Platform::Linux.base_dir(DirectoryKind::ProgramData, &redirected), None);
+    }
+
+    #[test]
+    fn test_windows_folders() {
+        let queried = RefCell::new(Vec::new());
+        let query = |folder| {
+            queried.borrow_mut().push(folder);
+            match folder {
+                KnownFolder::Profile => Some(PathBuf::from(r"C:\Users\Eve")),
+                KnownFolder::RoamingAppData => Some(PathBuf::from(r"E:\Roaming")),
+                _ => None,
+            }
+        };
+        let config = windows_folders(&[DirectoryKind::Config], query);
+        assert_eq!(Platform::Windows.base_dir(DirectoryKind::Config, &config), Some(PathBuf::from(r"E:\Roaming")));
+        assert_eq!(*queried.borrow(), vec![KnownFolder::RoamingAppData]);
+
+        queried.borrow_mut().clear();
+        let template = windows_folders(&[DirectoryKind::Template], query);
+        let expected = PathBuf::from(r"E:\Roaming\Microsoft\Windows\Templates");
+        assert_eq!(Platform::Windows.base_dir(DirectoryKind::Template, &template), Some(expected));
+        assert_eq!(*queried.borrow(), vec![KnownFolder::Templates, KnownFolder::RoamingAppData]);
+
+        let cache = windows_folders(&[DirectoryKind::Cache], query);
+        let expected = PathBuf::from(r"C:\Users\Eve\AppData\Local\cache");
+        assert_eq!(Platform::Windows.base_dir(DirectoryKind::Cache, &cache), Some(expected));
+    }
+
+    #[test]
+    fn test_backends_provide_same_kinds() {
+        use kind::DirectoryKind::*;
+        // the kinds each platform does not provide without further configuration
+        let platforms = vec![
+            (Platform::Linux, "/home/eve", vec![Preference, DataLow, ProgramData, UserProgram, Runtime, SystemFont]),
+            (Platform::MacOs, "/Users/eve", vec![Executable, Runtime, Template, DataLow, ProgramData, UserProgram, SystemFont]),
+            (Platform::Windows, r"C:\Users\Eve", vec![Executable, Runtime, Preference, Font]),
+        ];
+        for (platform, home_dir, missing) in platforms {
+            let backend = PlatformBackend::new(platform, PlatformFolders::new(home_dir));
+            let explanations = BaseDirectories::new().with_backend(backend).explain();
+            let kinds: Vec<DirectoryKind> = explanations.iter().map(|explanation| explanation.kind()).collect();
+            assert_eq!(kinds, DirectoryKind::all().collect::<Vec<_>>(), "{:?}", platform);
+            for explanation in explanations {
+                let kind = explanation.kind();
+                assert_eq!(explanation.path().is_none(), missing.contains(&kind), "{:?}: {}", platform, kind);
+            }
+        }
+    }
+}
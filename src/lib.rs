@@ -1,41 +1,216 @@
+#[cfg(unix)]
+extern crate libc;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
-#[cfg(target_os = "linux")]
+mod bundle;
+mod cache;
+mod classify;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod expand;
+mod home;
+mod kind;
+mod layout;
+#[cfg(all(unix, not(target_os = "macos")))]
 mod lin;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod passwd;
+mod policy;
+mod provenance;
+mod snapshot;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod sudo;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod system;
+mod systemd;
+#[cfg(all(unix, not(target_os = "macos")))]
+mod user;
+mod warnings;
+mod wine;
+mod wsl;
+mod xdg;
 #[cfg(target_os = "windows")]
 mod win;
 #[cfg(target_os = "macos")]
 mod mac;
 
-#[cfg(target_os = "linux")]
-pub use lin::OsBackend;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use lin::XdgBackend;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use lin::XdgBackend as OsBackend;
 #[cfg(target_os = "windows")]
 pub use win::OsBackend;
 #[cfg(target_os = "macos")]
 pub use mac::OsBackend;
 
+pub use bundle::BundleIdError;
+pub use bundle::MacAppDirectories;
+pub use classify::classify;
+pub use classify::classify_with_projects;
+pub use classify::Classification;
+pub use home::HomeDirError;
+pub use kind::DirectoryKind;
+pub use kind::ParseDirectoryKindError;
+pub use layout::KnownFolder;
+pub use layout::Platform;
+pub use layout::PlatformBackend;
+pub use layout::PlatformFolders;
+pub use policy::ConfigDirPolicy;
+pub use policy::LayoutPolicy;
+pub use provenance::Explanation;
+pub use provenance::Rejected;
+pub use provenance::RejectionReason;
+pub use provenance::Source;
+pub use snapshot::DirectoryChange;
+pub use snapshot::DirectorySnapshot;
+pub use systemd::CredentialError;
+pub use systemd::ServiceDirectories;
+pub use warnings::set_warning_handler;
+pub use wine::WinePrefix;
+pub use wsl::is_wsl;
+pub use wsl::is_wsl_with;
+pub use wsl::WindowsHostDirectories;
+
+use layout::project_dir_name;
+use layout::qualified_project_dir_name;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use expand::contract_path;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use expand::contract_path_redacted;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use expand::expand_path;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use expand::ExpandError;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use passwd::PasswdEntry;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use sudo::SudoPolicy;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use sudo::SudoUser;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use system::SystemDirectories;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use system::SystemProjectDirectories;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use user::UserDirectories;
+#[cfg(all(unix, not(target_os = "macos")))]
+pub use user::UserLookupError;
+
+/// The standard directories of the current user.
+///
+/// A value created with `BaseDirectories::new()` consults the environment of
+/// the current process on every call, while one created with
+/// `BaseDirectories::from_env` resolves directories from a fixed set of
+/// variables, e. g. to compute the directories of a different environment.
+///
+/// The layout of the directories is determined by a `BaseDirBackend`, by default
+/// the one of the platform the library is compiled for.
 #[derive(Debug, Clone)]
-pub struct BaseDirectories;
-
-/// A private abstraction over all OS specific modules
-trait BaseDirBackend {
-    fn home_dir() -> PathBuf;
-    fn cache_dir() -> PathBuf;
-    fn config_dir() -> PathBuf;
-    fn data_roaming_dir() -> PathBuf;
-    fn data_dir() -> PathBuf;
-    fn executable_dir() -> Option<PathBuf>;
-    fn runtime_dir() -> Option<PathBuf>;
-    fn audio_dir() -> PathBuf;
-    fn desktop_dir() -> PathBuf;
-    fn document_dir() -> PathBuf;
-    fn download_dir() -> PathBuf;
-    fn font_dir() -> Option<PathBuf>;
-    fn picture_dir() -> PathBuf;
-    fn public_dir() -> PathBuf;
-    fn template_dir() -> Option<PathBuf>;
-    fn video_dir() -> PathBuf;
+pub struct BaseDirectories {
+    // variables used instead of the environment of the current process
+    env: Option<HashMap<String, String>>,
+    backend: Arc<dyn BaseDirBackend>,
+}
+
+/// Computes the layout of the base directories for a platform.
+///
+/// The library ships `XdgBackend` for Unix platforms other than macOS, and a backend
+/// for each of Windows and macOS. Embedders can implement this trait for other
+/// layouts and pass it to `BaseDirectories::with_backend`. Implementations should
+/// read environment variables through `BaseDirectories::env_var`, so that they
+/// honor values created with `BaseDirectories::from_env`.
+///
+/// Each method computes the directory of the corresponding `BaseDirectories`
+/// method, e. g. `cache_dir` the one of `BaseDirectories::cache`. Methods
+/// returning `Option` return `None` if the layout has no such directory.
+pub trait BaseDirBackend: fmt::Debug + Send + Sync {
+    /// Returns the home directory of the user.
+    ///
+    /// Implementations may panic if there is none; they should then override
+    /// `try_home_dir` to report the reason instead.
+    fn home_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the home directory of the user, or why it cannot be determined.
+    ///
+    /// By default, this returns the result of `home_dir`.
+    fn try_home_dir(&self, dirs: &BaseDirectories) -> Result<PathBuf, HomeDirError> {
+        Ok(self.home_dir(dirs))
+    }
+    /// Returns the directory for non-essential data that can be recreated.
+    fn cache_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory for configuration files.
+    fn config_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory of property lists managed by the platform, e. g. `NSUserDefaults` on macOS.
+    fn preference_dir(&self, _dirs: &BaseDirectories) -> Option<PathBuf> {
+        None
+    }
+    /// Returns the directory for data that follows the user across machines.
+    fn data_roaming_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory for data that stays on the current machine.
+    fn data_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the local data directory writable by low-integrity processes, e. g. sandboxed browser plugins.
+    fn data_low_dir(&self, _dirs: &BaseDirectories) -> Option<PathBuf> {
+        None
+    }
+    /// Returns the data directory shared by all users of the machine.
+    fn program_data_dir(&self, _dirs: &BaseDirectories) -> Option<PathBuf> {
+        None
+    }
+    /// Returns the directory for executables installed by the user, or `None` if
+    /// the platform has no such directory.
+    fn executable_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf>;
+    /// Returns the directory programs installed for the current user only are placed in.
+    fn user_program_dir(&self, _dirs: &BaseDirectories) -> Option<PathBuf> {
+        None
+    }
+    /// Returns the directory of the fonts installed for all users.
+    fn system_font_dir(&self, _dirs: &BaseDirectories) -> Option<PathBuf> {
+        None
+    }
+    /// Returns the directory for sockets and other runtime files, or `None` if the
+    /// platform has no such directory or it is not configured, e. g. an unset `$XDG_RUNTIME_DIR`.
+    fn runtime_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf>;
+    /// Returns the directory for music and other audio files.
+    fn audio_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory whose files are shown on the desktop.
+    fn desktop_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory for documents.
+    fn document_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory downloaded files are saved to.
+    fn download_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory fonts are installed to for the current user, or `None`
+    /// if the platform does not support per-user fonts.
+    fn font_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf>;
+    /// Returns the directory for pictures.
+    fn picture_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory for files shared with other users.
+    fn public_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+    /// Returns the directory for document templates, or `None` if the platform has no such directory.
+    fn template_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf>;
+    /// Returns the directory for videos.
+    fn video_dir(&self, dirs: &BaseDirectories) -> PathBuf;
+
+    /// Explains where the directory of the given kind comes from.
+    ///
+    /// By default, the directory is attributed to `Source::Platform`.
+    fn explain(&self, dirs: &BaseDirectories, kind: DirectoryKind) -> Explanation {
+        let path = dirs.get(kind);
+        let source = path.as_ref().map(|_| Source::Platform);
+        Explanation::new(kind, path, source, Vec::new())
+    }
+
+    /// Explains all directories, in the order of `DirectoryKind::all()`.
+    fn explain_all(&self, dirs: &BaseDirectories) -> Vec<Explanation> {
+        DirectoryKind::all().map(|kind| self.explain(dirs, kind)).collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -48,19 +223,186 @@ pub struct ProjectDirectories {
     project_data_dir: PathBuf,
     project_data_local_dir: PathBuf,
     project_runtime_dir: Option<PathBuf>,
+
+    // directories provided by systemd, if running as a service
+    service_dirs: Option<ServiceDirectories>,
 }
 
 #[deny(missing_docs)]
 impl BaseDirectories {
+    /// Creates a value resolving directories from the environment of the current process.
+    pub fn new() -> BaseDirectories {
+        BaseDirectories { env: None, backend: Arc::new(OsBackend) }
+    }
+
+    /// Creates a value resolving directories from the given environment variables only.
+    ///
+    /// Variables missing from `vars` are treated as unset, e. g.
+    /// `BaseDirectories::from_env(vec![("HOME", "/home/eve")])` yields the
+    /// default directories below `/home/eve`. Passing `std::env::vars()`
+    /// freezes the current environment of the process.
+    pub fn from_env<I, K, V>(vars: I) -> BaseDirectories
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let env = vars.into_iter().map(|(key, value)| (key.into(), value.into())).collect();
+        BaseDirectories { env: Some(env), backend: Arc::new(OsBackend) }
+    }
+
+    /// Replaces the backend computing the layout of the directories.
+    ///
+    /// `BaseDirectories::new().with_backend(XdgBackend)` applies the XDG layout
+    /// on any Unix platform, for example.
+    pub fn with_backend<B: BaseDirBackend + 'static>(mut self, backend: B) -> BaseDirectories {
+        self.backend = Arc::new(backend);
+        self
+    }
+
+    /// Returns the value of the environment variable `key`, or `None` if it is
+    /// unset or not valid Unicode.
+    ///
+    /// Values created with `BaseDirectories::from_env` only consult their own variables.
+    pub fn env_var(&self, key: &str) -> Option<String> {
+        match self.env {
+            Some(ref vars) => vars.get(key).cloned(),
+            None => env::var(key).ok(),
+        }
+    }
+
+    /// Returns the path to the user's home directory, see `BaseDirectories::home_dir`.
+    pub fn home(&self) -> PathBuf {
+        self.backend.home_dir(self)
+    }
+
+    /// Returns the path to the user's home directory, or why it cannot be determined, see `BaseDirectories::try_home_dir`.
+    pub fn try_home(&self) -> Result<PathBuf, HomeDirError> {
+        self.backend.try_home_dir(self)
+    }
+
+    /// Returns the path to the user's cache directory, see `BaseDirectories::cache_dir`.
+    pub fn cache(&self) -> PathBuf {
+        self.backend.cache_dir(self)
+    }
+
+    /// Returns the path to the user's config directory, see `BaseDirectories::config_dir`.
+    pub fn config(&self) -> PathBuf {
+        self.backend.config_dir(self)
+    }
+
+    /// Returns the path to the user's preference directory, see `BaseDirectories::preference_dir`.
+    pub fn preference(&self) -> Option<PathBuf> {
+        self.backend.preference_dir(self)
+    }
+
+    /// Returns the path to the user's data directory, see `BaseDirectories::data_roaming_dir`.
+    pub fn data_roaming(&self) -> PathBuf {
+        self.backend.data_roaming_dir(self)
+    }
+
+    /// Returns the path to the user's local data directory, see `BaseDirectories::data_dir`.
+    pub fn data(&self) -> PathBuf {
+        self.backend.data_dir(self)
+    }
+
+    /// Returns the path to the user's executable directory, see `BaseDirectories::executable_dir`.
+    pub fn executable(&self) -> Option<PathBuf> {
+        self.backend.executable_dir(self)
+    }
+
+    /// Returns the path to the user's local data directory for low-integrity processes, see `BaseDirectories::data_low_dir`.
+    pub fn data_low(&self) -> Option<PathBuf> {
+        self.backend.data_low_dir(self)
+    }
+
+    /// Returns the path to the data directory shared by all users of the machine, see `BaseDirectories::program_data_dir`.
+    pub fn program_data(&self) -> Option<PathBuf> {
+        self.backend.program_data_dir(self)
+    }
+
+    /// Returns the path to the directory of programs installed for the current user only, see `BaseDirectories::user_program_dir`.
+    pub fn user_program(&self) -> Option<PathBuf> {
+        self.backend.user_program_dir(self)
+    }
+
+    /// Returns the path to the user's runtime directory, see `BaseDirectories::runtime_dir`.
+    pub fn runtime(&self) -> Option<PathBuf> {
+        self.backend.runtime_dir(self)
+    }
+
+    /// Returns the path to the user's audio directory, see `BaseDirectories::audio_dir`.
+    pub fn audio(&self) -> PathBuf {
+        self.backend.audio_dir(self)
+    }
+
+    /// Returns the path to the user's desktop directory, see `BaseDirectories::desktop_dir`.
+    pub fn desktop(&self) -> PathBuf {
+        self.backend.desktop_dir(self)
+    }
+
+    /// Returns the path to the user's document directory, see `BaseDirectories::document_dir`.
+    pub fn document(&self) -> PathBuf {
+        self.backend.document_dir(self)
+    }
+
+    /// Returns the path to the user's download directory, see `BaseDirectories::download_dir`.
+    pub fn download(&self) -> PathBuf {
+        self.backend.download_dir(self)
+    }
+
+    /// Returns the path to the user's font directory, see `BaseDirectories::font_dir`.
+    pub fn font(&self) -> Option<PathBuf> {
+        self.backend.font_dir(self)
+    }
+
+    /// Returns the path to the system-wide font directory, see `BaseDirectories::system_font_dir`.
+    pub fn system_font(&self) -> Option<PathBuf> {
+        self.backend.system_font_dir(self)
+    }
+
+    /// Returns the path to the user's picture directory, see `BaseDirectories::picture_dir`.
+    pub fn picture(&self) -> PathBuf {
+        self.backend.picture_dir(self)
+    }
+
+    /// Returns the path to the user's public directory, see `BaseDirectories::public_dir`.
+    pub fn public(&self) -> PathBuf {
+        self.backend.public_dir(self)
+    }
+
+    /// Returns the path to the user's template directory, see `BaseDirectories::template_dir`.
+    pub fn template(&self) -> Option<PathBuf> {
+        self.backend.template_dir(self)
+    }
+
+    /// Returns the path to the user's video directory, see `BaseDirectories::video_dir`.
+    pub fn video(&self) -> PathBuf {
+        self.backend.video_dir(self)
+    }
+
     /// Returns the path to the user's home directory.
     ///
-    /// |Platform | Value                | Example       |
-    /// | ------- | -------------------- | ------------- |
-    /// | Linux   | `$HOME`              | /home/eve/    |
-    /// | macOS   | `$HOME`              | /Users/eve/   |
-    /// | Windows | `{FOLDERID_Profile}` | C:\Users\Eve\ |
+    /// |Platform | Value                                | Example       |
+    /// | ------- | ------------------------------------ | ------------- |
+    /// | Linux   | `$HOME` or the home in `/etc/passwd` | /home/eve/    |
+    /// | macOS   | `$HOME`                              | /Users/eve/   |
+    /// | Windows | `{FOLDERID_Profile}`                 | C:\Users\Eve\ |
+    ///
+    /// On Linux, this function panics if neither `$HOME` nor the password database
+    /// entry of the effective uid provide an absolute path, see `try_home_dir`.
     pub fn home_dir() -> PathBuf {
-        OsBackend::home_dir()
+        BaseDirectories::new().home()
+    }
+
+    /// Returns the path to the user's home directory, like `home_dir`, or why it
+    /// cannot be determined.
+    ///
+    /// On Linux, the error tells whether `$HOME` is unusable and the password
+    /// database has no entry for the effective uid, lists a relative home
+    /// directory, or cannot be queried at all.
+    pub fn try_home_dir() -> Result<PathBuf, HomeDirError> {
+        BaseDirectories::new().try_home()
     }
 
     /// Returns the path to the user's cache directory.
@@ -71,7 +413,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Library/Caches/`           | /Users/eve/Library/Caches/        |
     /// | Windows | `{FOLDERID_LocalAppData}\cache\`  | C:\Users\Eve\AppData\Local\cache\ |
     pub fn cache_dir() -> PathBuf {
-        OsBackend::cache_dir()
+        BaseDirectories::new().cache()
     }
 
     /// Returns the path to the user's config directory.
@@ -82,7 +424,21 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Library/Preferences/`       | /Users/eve/Library/Preferences/ |
     /// | Windows | `{FOLDERID_RoamingAppData}`        | C:\Users\Eve\AppData\Roaming\   |
     pub fn config_dir() -> PathBuf {
-        OsBackend::config_dir()
+        BaseDirectories::new().config()
+    }
+
+    /// Returns the path to the user's preference directory.
+    ///
+    /// On macOS, this directory is reserved for property lists managed by
+    /// `NSUserDefaults`; other configuration belongs into `config_dir`.
+    ///
+    /// |Platform | Value                        | Example                         |
+    /// | ------- | ---------------------------- | ------------------------------- |
+    /// | Linux   | –                            | –                               |
+    /// | macOS   | `$HOME/Library/Preferences/` | /Users/eve/Library/Preferences/ |
+    /// | Windows | –                            | –                               |
+    pub fn preference_dir() -> Option<PathBuf> {
+        BaseDirectories::new().preference()
     }
 
     /// Returns the path to the user's data directory.
@@ -93,7 +449,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Library/Application Support/`  | /Users/eve/Library/Application Support/ |
     /// | Windows | `{FOLDERID_RoamingAppData}`           | C:\Users\Eve\AppData\Roaming\           |
     pub fn data_roaming_dir() -> PathBuf {
-        OsBackend::data_roaming_dir()
+        BaseDirectories::new().data_roaming()
     }
 
     /// Returns the path to the user's local data directory.
@@ -104,7 +460,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Library/Application Support/`  | /Users/eve/Library/Application Support/ |
     /// | Windows | `{FOLDERID_LocalAppData}`             | C:\Users\Eve\AppData\Local\             |
     pub fn data_dir() -> PathBuf {
-        OsBackend::data_dir()
+        BaseDirectories::new().data()
     }
 
     /// Returns the path to the user's executable directory.
@@ -115,7 +471,40 @@ impl BaseDirectories {
     /// | macOS   | –                                                              | –                        |
     /// | Windows | –                                                              | –                        |
     pub fn executable_dir() -> Option<PathBuf> {
-        OsBackend::executable_dir()
+        BaseDirectories::new().executable()
+    }
+
+    /// Returns the path to the user's local data directory for low-integrity processes.
+    ///
+    /// |Platform | Value                        | Example                         |
+    /// | ------- | ---------------------------- | ------------------------------- |
+    /// | Linux   | –                            | –                               |
+    /// | macOS   | –                            | –                               |
+    /// | Windows | `{FOLDERID_LocalAppDataLow}` | C:\Users\Eve\AppData\LocalLow\ |
+    pub fn data_low_dir() -> Option<PathBuf> {
+        BaseDirectories::new().data_low()
+    }
+
+    /// Returns the path to the data directory shared by all users of the machine.
+    ///
+    /// |Platform | Value                    | Example         |
+    /// | ------- | ------------------------ | --------------- |
+    /// | Linux   | –                        | –               |
+    /// | macOS   | –                        | –               |
+    /// | Windows | `{FOLDERID_ProgramData}` | C:\ProgramData\ |
+    pub fn program_data_dir() -> Option<PathBuf> {
+        BaseDirectories::new().program_data()
+    }
+
+    /// Returns the path to the directory of programs installed for the current user only.
+    ///
+    /// |Platform | Value                         | Example                              |
+    /// | ------- | ----------------------------- | ------------------------------------ |
+    /// | Linux   | –                             | –                                    |
+    /// | macOS   | –                             | –                                    |
+    /// | Windows | `{FOLDERID_UserProgramFiles}` | C:\Users\Eve\AppData\Local\Programs\ |
+    pub fn user_program_dir() -> Option<PathBuf> {
+        BaseDirectories::new().user_program()
     }
 
     /// Returns the path to the user's runtime directory.
@@ -126,7 +515,7 @@ impl BaseDirectories {
     /// | macOS   | –                  | –               |
     /// | Windows | –                  | –               |
     pub fn runtime_dir() -> Option<PathBuf> {
-        OsBackend::runtime_dir()
+        BaseDirectories::new().runtime()
     }
 
     /// Returns the path to the user's audio directory.
@@ -137,7 +526,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Music/`     | /Users/eve/Music/   |
     /// | Windows | `{FOLDERID_Music}` | C:\Users\Eve\Music\ |
     pub fn audio_dir() -> PathBuf {
-        OsBackend::audio_dir()
+        BaseDirectories::new().audio()
     }
 
     /// Returns the path to the user's desktop directory.
@@ -148,7 +537,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Desktop/`     | /Users/eve/Desktop/   |
     /// | Windows | `{FOLDERID_Desktop}` | C:\Users\Eve\Desktop\ |
     pub fn desktop_dir() -> PathBuf {
-        OsBackend::desktop_dir()
+        BaseDirectories::new().desktop()
     }
 
     /// Returns the path to the user's document directory.
@@ -159,7 +548,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Documents/`     | /Users/eve/Documents/   |
     /// | Windows | `{FOLDERID_Documents}` | C:\Users\Eve\Documents\ |
     pub fn document_dir() -> PathBuf {
-        OsBackend::document_dir()
+        BaseDirectories::new().document()
     }
 
     /// Returns the path to the user's download directory.
@@ -170,7 +559,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Downloads/`     | /Users/eve/Downloads/   |
     /// | Windows | `{FOLDERID_Downloads}` | C:\Users\Eve\Downloads\ |
     pub fn download_dir() -> PathBuf {
-        OsBackend::download_dir()
+        BaseDirectories::new().download()
     }
 
     /// Returns the path to the user's font directory.
@@ -181,7 +570,21 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Library/Fonts/`                                 | /Users/eve/Library/Fonts/     |
     /// | Windows | –                                                      | –                             |
     pub fn font_dir() -> Option<PathBuf> {
-        OsBackend::font_dir()
+        BaseDirectories::new().font()
+    }
+
+    /// Returns the path to the system-wide font directory.
+    ///
+    /// On Windows, fonts can only be installed there with administrator rights;
+    /// `font_dir` is the directory of the fonts of the current user.
+    ///
+    /// |Platform | Value              | Example           |
+    /// | ------- | ------------------ | ----------------- |
+    /// | Linux   | –                  | –                 |
+    /// | macOS   | –                  | –                 |
+    /// | Windows | `{FOLDERID_Fonts}` | C:\Windows\Fonts\ |
+    pub fn system_font_dir() -> Option<PathBuf> {
+        BaseDirectories::new().system_font()
     }
 
     /// Returns the path to the user's picture directory.
@@ -192,7 +595,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Pictures/`     | /Users/eve/Pictures/   |
     /// | Windows | `{FOLDERID_Pictures}` | C:\Users\Eve\Pictures\ |
     pub fn picture_dir() -> PathBuf {
-        OsBackend::picture_dir()
+        BaseDirectories::new().picture()
     }
 
     /// Returns the path to the user's public directory.
@@ -203,7 +606,7 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Public/`       | /Users/eve/Public/ |
     /// | Windows | `{FOLDERID_Public}`   | C:\Users\Public\   |
     pub fn public_dir() -> PathBuf {
-        OsBackend::public_dir()
+        BaseDirectories::new().public()
     }
 
     /// Returns the path to the user's template directory.
@@ -214,7 +617,7 @@ impl BaseDirectories {
     /// | macOS   | –                      | –                                                         |
     /// | Windows | `{FOLDERID_Templates}` | C:\Users\Eve\AppData\Roaming\Microsoft\Windows\Templates\ |
     pub fn template_dir() -> Option<PathBuf> {
-        OsBackend::template_dir()
+        BaseDirectories::new().template()
     }
 
     /// Returns the path to the user's video directory.
@@ -225,11 +628,93 @@ impl BaseDirectories {
     /// | macOS   | `$HOME/Movies/`     | /Users/eve/Movies/   |
     /// | Windows | `{FOLDERID_Videos}` | C:\Users\Eve\Videos\ |
     pub fn video_dir() -> PathBuf {
-        OsBackend::video_dir()
+        BaseDirectories::new().video()
+    }
+}
+
+impl Default for BaseDirectories {
+    fn default() -> BaseDirectories {
+        BaseDirectories::new()
     }
 }
 
 impl ProjectDirectories {
+    /// Computes the project directories below the given base directories, e. g.
+    /// those of a custom `BaseDirBackend`.
+    ///
+    /// Like `from_unprocessed_string` on Linux, the directories systemd provides
+    /// to services take precedence, as read from the environment of `base_dirs`.
+    /// No platform-specific nesting is applied.
+    pub fn from_unprocessed_string_with_base_dirs(value: &str, base_dirs: &BaseDirectories) -> ProjectDirectories {
+        let service_dirs = ServiceDirectories::from_lookup(|key| base_dirs.env_var(key));
+        ProjectDirectories::with_service_dirs(value, service_dirs, |kind| base_dirs.get(kind).map(|dir| dir.join(value)))
+    }
+
+    /// Assembles the project directories `value` from the directories systemd
+    /// provides to services, if any, and otherwise from `project_dir`, which
+    /// returns the project directory of the given kind.
+    ///
+    /// Panics if `project_dir` has no cache, config or data directory, which only
+    /// happens if the home directory is unknown, like `BaseDirectories::home_dir` does.
+    fn with_service_dirs<F>(value: &str, service_dirs: Option<ServiceDirectories>, project_dir: F) -> ProjectDirectories
+    where
+        F: Fn(DirectoryKind) -> Option<PathBuf>,
+    {
+        let service_dir = |dirs: fn(&ServiceDirectories) -> &[PathBuf]| {
+            service_dirs.as_ref().and_then(|s| dirs(s).first().cloned())
+        };
+        let required_dir = |kind: DirectoryKind| {
+            project_dir(kind).unwrap_or_else(|| {
+                panic!("the {} directory of {:?} cannot be determined, as the home directory is unknown", kind, value)
+            })
+        };
+        let project_cache_dir = service_dir(ServiceDirectories::cache_dirs).unwrap_or_else(|| required_dir(DirectoryKind::Cache));
+        let project_config_dir = service_dir(ServiceDirectories::config_dirs).unwrap_or_else(|| required_dir(DirectoryKind::Config));
+        let (project_data_dir, project_data_local_dir) = match service_dir(ServiceDirectories::state_dirs) {
+            Some(state_dir) => (state_dir.clone(), state_dir),
+            None => (required_dir(DirectoryKind::DataRoaming), required_dir(DirectoryKind::Data)),
+        };
+        let project_runtime_dir = service_dir(ServiceDirectories::runtime_dirs).or_else(|| project_dir(DirectoryKind::Runtime));
+
+        ProjectDirectories {
+            project_name: String::from(value),
+            project_cache_dir,
+            project_config_dir,
+            project_data_dir,
+            project_data_local_dir,
+            project_runtime_dir,
+            service_dirs,
+        }
+    }
+
+    /// Like `from_project_name`, but below the given base directories, see
+    /// `from_unprocessed_string_with_base_dirs`.
+    pub fn from_project_name_with_base_dirs(project_name: &str, base_dirs: &BaseDirectories) -> ProjectDirectories {
+        let name = project_dir_name(Platform::current(), project_name);
+        ProjectDirectories::from_unprocessed_string_with_base_dirs(&name, base_dirs)
+    }
+
+    /// Like `from_qualified_project_name`, but below the given base directories, see
+    /// `from_unprocessed_string_with_base_dirs`.
+    pub fn from_qualified_project_name_with_base_dirs(qualified_project_name: &str, base_dirs: &BaseDirectories) -> ProjectDirectories {
+        let name = qualified_project_dir_name(Platform::current(), qualified_project_name);
+        ProjectDirectories::from_unprocessed_string_with_base_dirs(&name, base_dirs)
+    }
+
+    /// Like `from_project_name`, but derived from `BaseDirectories::cached()`,
+    /// so the environment is not read again.
+    ///
+    /// Like `from_project_name`, this panics if the cached snapshot has no home directory.
+    pub fn from_project_name_cached(project_name: &str) -> ProjectDirectories {
+        BaseDirectories::cached().project_dirs_from_project_name(project_name)
+    }
+
+    /// Like `from_qualified_project_name`, but derived from `BaseDirectories::cached()`,
+    /// so the environment is not read again.
+    pub fn from_qualified_project_name_cached(qualified_project_name: &str) -> ProjectDirectories {
+        BaseDirectories::cached().project_dirs_from_qualified_project_name(qualified_project_name)
+    }
+
     pub fn project_name(&self) -> &str {
         self.project_name.as_str()
     }
@@ -246,7 +731,10 @@ impl ProjectDirectories {
         self.project_data_local_dir.as_path()
     }
     pub fn project_runtime_dir(&self) -> Option<&Path> {
-        self.project_runtime_dir.as_ref().map(|p| p.as_path())
+        self.project_runtime_dir.as_deref()
+    }
+    pub fn service_directories(&self) -> Option<&ServiceDirectories> {
+        self.service_dirs.as_ref()
     }
 }
 
@@ -256,9 +744,56 @@ fn strip_qualification(name: &str) -> &str {
         .unwrap_or(name)
 }
 
+fn trim_and_replace_spaces_with_hyphens_then_lowercase(name: &str) -> String {
+    let mut buf = String::with_capacity(name.len());
+    let mut parts = name.split_whitespace();
+    let mut current_part = parts.next();
+    while current_part.is_some() {
+        let value = current_part.unwrap().to_lowercase();
+        buf.push_str(&value);
+        current_part = parts.next();
+        if current_part.is_some() {
+            buf.push('-');
+        }
+    }
+    buf
+}
+
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+    use std::path::PathBuf;
     use strip_qualification;
+    use trim_and_replace_spaces_with_hyphens_then_lowercase;
+    use BaseDirBackend;
+    use BaseDirectories;
+    use DirectoryKind;
+    use ProjectDirectories;
+    use Source;
+
+    #[derive(Debug)]
+    struct PortableBackend;
+
+    impl BaseDirBackend for PortableBackend {
+        fn home_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+            PathBuf::from(dirs.env_var("PORTABLE_ROOT").unwrap())
+        }
+        fn cache_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs).join("cache") }
+        fn config_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs).join("config") }
+        fn data_roaming_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs).join("data") }
+        fn data_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs).join("data") }
+        fn executable_dir(&self, _: &BaseDirectories) -> Option<PathBuf> { None }
+        fn runtime_dir(&self, _: &BaseDirectories) -> Option<PathBuf> { None }
+        fn audio_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs) }
+        fn desktop_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs) }
+        fn document_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs) }
+        fn download_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs) }
+        fn font_dir(&self, _: &BaseDirectories) -> Option<PathBuf> { None }
+        fn picture_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs) }
+        fn public_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs) }
+        fn template_dir(&self, _: &BaseDirectories) -> Option<PathBuf> { None }
+        fn video_dir(&self, dirs: &BaseDirectories) -> PathBuf { self.home_dir(dirs) }
+    }
 
     #[test]
     fn test_strip_qualification() {
@@ -270,4 +805,57 @@ mod tests {
         let expected2 = "BarApp";
         assert_eq!(actual2, expected2);
     }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_base_directories_from_env() {
+        let dirs = BaseDirectories::from_env(vec![("HOME", "/home/eve"), ("XDG_CACHE_HOME", "/var/tmp/eve")]);
+        assert_eq!(dirs.home(), PathBuf::from("/home/eve"));
+        assert_eq!(dirs.try_home().unwrap(), PathBuf::from("/home/eve"));
+        assert_eq!(dirs.cache(), PathBuf::from("/var/tmp/eve"));
+        assert_eq!(dirs.config(), PathBuf::from("/home/eve/.config"));
+        assert_eq!(dirs.runtime(), None);
+    }
+
+    #[test]
+    fn test_custom_backend() {
+        let dirs = BaseDirectories::from_env(vec![("PORTABLE_ROOT", "/media/stick")]).with_backend(PortableBackend);
+        assert_eq!(dirs.config(), PathBuf::from("/media/stick/config"));
+        assert_eq!(dirs.explain_kind(DirectoryKind::Cache).source(), Some(&Source::Platform));
+        assert_eq!(dirs.explain_kind(DirectoryKind::Runtime).source(), None);
+
+        let project_dirs = ProjectDirectories::from_unprocessed_string_with_base_dirs("app", &dirs);
+        assert_eq!(project_dirs.project_cache_dir(), Path::new("/media/stick/cache/app"));
+        assert_eq!(project_dirs.project_runtime_dir(), None);
+
+        let service = BaseDirectories::from_env(vec![("PORTABLE_ROOT", "/media/stick"), ("STATE_DIRECTORY", "/var/lib/app")])
+            .with_backend(PortableBackend);
+        let project_dirs = ProjectDirectories::from_project_name_with_base_dirs("app", &service);
+        assert_eq!(project_dirs.project_data_dir(), Path::new("/var/lib/app"));
+        assert_eq!(project_dirs.project_config_dir(), Path::new("/media/stick/config/app"));
+        assert!(project_dirs.service_directories().is_some());
+    }
+
+    #[test]
+    fn test_trim_and_replace_spaces_with_hyphens_then_lowercase() {
+        let input1 = "Bar App";
+        let actual1 = trim_and_replace_spaces_with_hyphens_then_lowercase(input1);
+        let expected1 = "bar-app";
+        assert_eq!(expected1, actual1);
+
+        let input2 = "BarApp-Foo";
+        let actual2 = trim_and_replace_spaces_with_hyphens_then_lowercase(input2);
+        let expected2 = "barapp-foo";
+        assert_eq!(expected2, actual2);
+
+        let input3 = " Bar App ";
+        let actual3 = trim_and_replace_spaces_with_hyphens_then_lowercase(input3);
+        let expected3 = "bar-app";
+        assert_eq!(expected3, actual3);
+
+        let input4 = "  Bar  App  ";
+        let actual4 = trim_and_replace_spaces_with_hyphens_then_lowercase(input4);
+        let expected4 = "bar-app";
+        assert_eq!(expected4, actual4);
+    }
 }
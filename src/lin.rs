@@ -1,134 +1,132 @@
+use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
-use std::process::Command;
+
+use libc;
 
 use BaseDirBackend;
+use BaseDirectories;
+use home::HomeDirError;
+use kind::DirectoryKind;
+use passwd;
+use passwd::PasswdDatabase;
+use provenance::Explanation;
 use ProjectDirectories;
 use strip_qualification;
+use trim_and_replace_spaces_with_hyphens_then_lowercase;
+use warnings;
+use xdg::explain_with_home;
+use xdg::parse_user_dirs_lines;
+
+/// The layout defined by the XDG base directory and user directory specifications.
+///
+/// This is the default backend on all Unix platforms except macOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct XdgBackend;
+
+impl BaseDirBackend for XdgBackend {
+    fn home_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        self.try_home_dir(dirs).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_home_dir(&self, dirs: &BaseDirectories) -> Result<PathBuf, HomeDirError> {
+        resolve_home(dirs).map(|home| home.into_path().unwrap())
+    }
+
+    fn cache_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Cache).unwrap()
+    }
 
-pub struct OsBackend;
-impl BaseDirBackend for OsBackend {
-    fn home_dir() -> PathBuf {
-        env::home_dir().unwrap()
+    fn config_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Config).unwrap()
     }
 
-    fn cache_dir() -> PathBuf {
-        env::var("XDG_CACHE_HOME")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap_or(OsBackend::home_dir().join(".cache"))
+    fn data_roaming_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::DataRoaming).unwrap()
     }
 
-    fn config_dir() -> PathBuf {
-        env::var("XDG_CONFIG_HOME")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap_or(OsBackend::home_dir().join(".config"))
+    fn data_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Data).unwrap()
     }
 
-    fn data_roaming_dir() -> PathBuf {
-        env::var("XDG_DATA_HOME")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap_or(OsBackend::home_dir().join(".local/share"))
+    fn executable_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        resolve(dirs, DirectoryKind::Executable)
     }
 
-    fn data_dir() -> PathBuf {
-        env::var("XDG_DATA_HOME")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap_or(OsBackend::home_dir().join(".local/share"))
+    fn runtime_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        resolve(dirs, DirectoryKind::Runtime)
     }
 
-    fn executable_dir() -> Option<PathBuf> {
-        Some(
-            env::var("XDG_BIN_HOME")
-                .ok()
-                .and_then(is_absolute_path)
-                .unwrap_or({
-                    let mut new_dir = OsBackend::data_dir().clone();
-                    new_dir.pop();
-                    new_dir.push("bin");
-                    new_dir
-                }),
-        )
+    fn audio_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Audio).unwrap()
     }
 
-    fn runtime_dir() -> Option<PathBuf> {
-        env::var("XDG_RUNTIME_DIR").ok().and_then(is_absolute_path)
+    fn desktop_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Desktop).unwrap()
     }
 
-    fn audio_dir() -> PathBuf {
-        run_xdg_user_dir_command("MUSIC")
+    fn document_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Document).unwrap()
     }
 
-    fn desktop_dir() -> PathBuf {
-        run_xdg_user_dir_command("DESKTOP")
+    fn download_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Download).unwrap()
     }
 
-    fn document_dir() -> PathBuf {
-        run_xdg_user_dir_command("DOCUMENTS")
+    fn font_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        resolve(dirs, DirectoryKind::Font)
     }
 
-    fn download_dir() -> PathBuf {
-        run_xdg_user_dir_command("DOWNLOAD")
+    fn picture_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Picture).unwrap()
     }
 
-    fn font_dir() -> Option<PathBuf> {
-        Some(OsBackend::data_dir().join("fonts"))
+    fn public_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Public).unwrap()
     }
 
-    fn picture_dir() -> PathBuf {
-        run_xdg_user_dir_command("PICTURES")
+    fn template_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        resolve(dirs, DirectoryKind::Template)
     }
 
-    fn public_dir() -> PathBuf {
-        run_xdg_user_dir_command("PUBLICSHARE")
+    fn video_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        resolve(dirs, DirectoryKind::Video).unwrap()
     }
 
-    fn template_dir() -> Option<PathBuf> {
-        Some(run_xdg_user_dir_command("TEMPLATES"))
+    fn explain(&self, dirs: &BaseDirectories, kind: DirectoryKind) -> Explanation {
+        explain(dirs, kind)
     }
 
-    fn video_dir() -> PathBuf {
-        run_xdg_user_dir_command("VIDEOS")
+    fn explain_all(&self, dirs: &BaseDirectories) -> Vec<Explanation> {
+        explain_all(dirs)
     }
 }
 
+/// Resolves the home directory from the environment and the password database
+/// entry of the effective uid, reporting ignored values.
+fn resolve_home(dirs: &BaseDirectories) -> Result<Explanation, HomeDirError> {
+    let uid = unsafe { libc::geteuid() };
+    let mut rejected = Vec::new();
+    let home_dir = passwd::home_dir_with(&|key: &str| dirs.env_var(key), &PasswdDatabase::System, uid, &mut rejected);
+    warnings::report(dirs, &rejected);
+    home_dir.map(|(home_dir, source)| Explanation::new(DirectoryKind::Home, Some(home_dir), Some(source), rejected))
+}
+
+/// Resolves the directory of the given kind from the environment, reporting ignored values.
+///
+/// Panics if the home directory cannot be determined, see `resolve_home`.
+fn resolve(dirs: &BaseDirectories, kind: DirectoryKind) -> Option<PathBuf> {
+    let home = resolve_home(dirs).unwrap_or_else(|err| panic!("{}", err));
+    let explanation = explain_with_home(kind, &|key: &str| dirs.env_var(key), &read_file, &home);
+    warnings::report(dirs, explanation.rejected());
+    explanation.into_path()
+}
+
 impl ProjectDirectories {
     pub fn from_unprocessed_string(value: &str) -> ProjectDirectories {
-        let project_name = String::from(value);
-        let home_dir = env::home_dir().unwrap();
-        let project_cache_dir = env::var("XDG_CACHE_HOME")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap_or(home_dir.join(".cache"))
-            .join(&value);
-        let project_config_dir = env::var("XDG_CONFIG_HOME")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap_or(home_dir.join(".config"))
-            .join(&value);
-        let project_data_dir = env::var("XDG_DATA_HOME")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap_or(home_dir.join(".local/share"))
-            .join(&value);
-        let project_data_local_dir = project_data_dir.clone();
-        let project_runtime_dir = env::var("XDG_RUNTIME_DIR")
-            .ok()
-            .and_then(is_absolute_path)
-            .unwrap()
-            .join(&value);
-
-        ProjectDirectories {
-            project_name: project_name,
-            project_cache_dir: project_cache_dir,
-            project_config_dir: project_config_dir,
-            project_data_dir: project_data_dir,
-            project_data_local_dir: project_data_local_dir,
-            project_runtime_dir: Some(project_runtime_dir),
-        }
+        ProjectDirectories::from_unprocessed_string_with_base_dirs(value, &BaseDirectories::new())
     }
 
     pub fn from_project_name(project_name: &str) -> ProjectDirectories {
@@ -140,67 +138,183 @@ impl ProjectDirectories {
         let name = strip_qualification(qualified_project_name).to_lowercase();
         ProjectDirectories::from_unprocessed_string(name.trim())
     }
-}
 
-fn is_absolute_path(path: String) -> Option<PathBuf> {
-    let path = PathBuf::from(path);
-    if path.is_absolute() {
-        Some(path)
-    } else {
-        None
+    pub fn from_qualified_project_name_nested(qualified_project_name: &str) -> ProjectDirectories {
+        ProjectDirectories::from_qualified_project_name(qualified_project_name)
     }
 }
 
-fn run_xdg_user_dir_command(arg: &str) -> PathBuf {
-    let mut out = Command::new("xdg-user-dir")
-        .arg(arg)
-        .output()
-        .expect("failed to execute process")
-        .stdout;
-    let out_len = out.len();
-    out.truncate(out_len - 1);
-    PathBuf::from(String::from_utf8(out).unwrap())
+/// Returns the directory described by the XDG base directory variable `var`,
+/// as read through `lookup`.
+///
+/// Values which are not absolute are ignored. Unset or ignored variables fall back
+/// to their defaults below `home_dir`, except for `XDG_RUNTIME_DIR`, which has none.
+/// Returns `None` for variables not listed in `XDG_BASE_DIR_VARIABLES`.
+pub fn xdg_base_dir<F: Fn(&str) -> Option<String>>(var: &str, lookup: &F, home_dir: &Path) -> Option<PathBuf> {
+    let kind = match var {
+        "XDG_CACHE_HOME" => DirectoryKind::Cache,
+        "XDG_CONFIG_HOME" => DirectoryKind::Config,
+        "XDG_DATA_HOME" => DirectoryKind::Data,
+        "XDG_BIN_HOME" => DirectoryKind::Executable,
+        "XDG_RUNTIME_DIR" => DirectoryKind::Runtime,
+        _ => return None,
+    };
+    let home = Explanation::new(DirectoryKind::Home, Some(home_dir.to_path_buf()), None, Vec::new());
+    explain_with_home(kind, lookup, &read_file, &home).into_path()
+}
+
+/// Explains how the directory of the given kind is resolved from the environment
+/// of `dirs`, see `BaseDirectories::explain_kind`.
+pub fn explain(dirs: &BaseDirectories, kind: DirectoryKind) -> Explanation {
+    let uid = unsafe { libc::geteuid() };
+    explain_with(kind, &|key: &str| dirs.env_var(key), &PasswdDatabase::System, uid)
 }
 
-fn trim_and_replace_spaces_with_hyphens_then_lowercase(name: &str) -> String {
-    let mut buf = String::with_capacity(name.len());
-    let mut parts = name.split_whitespace();
-    let mut current_part = parts.next();
-    while current_part.is_some() {
-        let value = current_part.unwrap().to_lowercase();
-        buf.push_str(&value);
-        current_part = parts.next();
-        if current_part.is_some() {
-            buf.push('-');
+/// Explains all directories, in the order of `DirectoryKind::all()`.
+///
+/// The environment is read only once, so the result is consistent even if
+/// another thread modifies the environment in the meantime.
+pub fn explain_all(dirs: &BaseDirectories) -> Vec<Explanation> {
+    let captured: HashMap<String, String>;
+    let vars = match dirs.env {
+        Some(ref vars) => vars,
+        None => {
+            captured = env::vars_os()
+                .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+                .collect();
+            &captured
         }
+    };
+    let lookup = |key: &str| vars.get(key).cloned();
+    let uid = unsafe { libc::geteuid() };
+    explain_all_with(&lookup, &PasswdDatabase::System, uid)
+}
+
+/// Like `explain`, but reads variables through `lookup` and falls back to the
+/// entry for `uid` in `database` for the home directory.
+pub fn explain_with<F: Fn(&str) -> Option<String>>(kind: DirectoryKind, lookup: &F, database: &PasswdDatabase, uid: u32) -> Explanation {
+    explain_with_home(kind, lookup, &read_file, &explain_home(lookup, database, uid))
+}
+
+/// Like `explain_all`, but reads variables through `lookup`, see `explain_with`.
+pub fn explain_all_with<F: Fn(&str) -> Option<String>>(lookup: &F, database: &PasswdDatabase, uid: u32) -> Vec<Explanation> {
+    let home = explain_home(lookup, database, uid);
+    DirectoryKind::all().map(|kind| explain_with_home(kind, lookup, &read_file, &home)).collect()
+}
+
+/// Explains the home directory; why it cannot be determined is reported by `BaseDirectories::try_home`.
+fn explain_home<F: Fn(&str) -> Option<String>>(lookup: &F, database: &PasswdDatabase, uid: u32) -> Explanation {
+    let mut rejected = Vec::new();
+    match passwd::home_dir_with(lookup, database, uid, &mut rejected) {
+        Ok((home_dir, source)) => Explanation::new(DirectoryKind::Home, Some(home_dir), Some(source), rejected),
+        Err(_) => Explanation::new(DirectoryKind::Home, None, None, rejected),
     }
-    buf
+}
+
+/// Reads a file like `user-dirs.dirs`, treating unreadable files as missing.
+fn read_file(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
+
+pub fn env_var(key: &str) -> Option<String> {
+    env::var(key).ok()
+}
+
+/// Parses the contents of a `user-dirs.dirs` file.
+///
+/// Returns the directories keyed by their names without the `XDG_` prefix and
+/// `_DIR` suffix, e. g. `DESKTOP`. Following the xdg-user-dirs format, values
+/// must be quoted and are either absolute or relative to `$HOME`.
+pub fn parse_user_dirs(content: &str, home_dir: &Path) -> HashMap<String, PathBuf> {
+    parse_user_dirs_lines(content, home_dir)
+        .into_iter()
+        .filter_map(|entry| match entry.value {
+            Ok(dir) => Some((entry.name, dir)),
+            Err(_) => None,
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use lin::trim_and_replace_spaces_with_hyphens_then_lowercase;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use kind::DirectoryKind;
+    use lin::explain_with;
+    use lin::parse_user_dirs;
+    use passwd::PasswdDatabase;
+    use provenance::RejectionReason;
+    use provenance::Source;
+
+    #[test]
+    fn test_parse_user_dirs() {
+        let content = "# comment\n\
+                       XDG_DESKTOP_DIR=\"$HOME/Desktop\"\n\
+                       XDG_MUSIC_DIR=\"/srv/music\"\n\
+                       XDG_PUBLICSHARE_DIR=\"$HOME\"\n\
+                       XDG_VIDEOS_DIR=\"relative\"\n\
+                       XDG_TEMPLATES_DIR=$HOME/Templates\n";
+        let actual = parse_user_dirs(content, Path::new("/home/eve"));
+        assert_eq!(actual.len(), 3);
+        assert_eq!(actual["DESKTOP"], PathBuf::from("/home/eve/Desktop"));
+        assert_eq!(actual["MUSIC"], PathBuf::from("/srv/music"));
+        assert_eq!(actual["PUBLICSHARE"], PathBuf::from("/home/eve"));
+    }
 
     #[test]
-    fn test_trim_and_replace_spaces_with_hyphens_then_lowercase() {
-        let input1 = "Bar App";
-        let actual1 = trim_and_replace_spaces_with_hyphens_then_lowercase(input1);
-        let expected1 = "bar-app";
-        assert_eq!(expected1, actual1);
-
-        let input2 = "BarApp-Foo";
-        let actual2 = trim_and_replace_spaces_with_hyphens_then_lowercase(input2);
-        let expected2 = "barapp-foo";
-        assert_eq!(expected2, actual2);
-
-        let input3 = " Bar App ";
-        let actual3 = trim_and_replace_spaces_with_hyphens_then_lowercase(input3);
-        let expected3 = "bar-app";
-        assert_eq!(expected3, actual3);
-
-        let input4 = "  Bar  App  ";
-        let actual4 = trim_and_replace_spaces_with_hyphens_then_lowercase(input4);
-        let expected4 = "bar-app";
-        assert_eq!(expected4, actual4);
+    fn test_explain_with() {
+        let root = env::temp_dir().join(format!("directories-explain-{}", ::std::process::id()));
+        let home = root.join("home");
+        let passwd = root.join("passwd");
+        fs::create_dir_all(home.join(".config")).unwrap();
+        fs::write(&passwd, format!("root:x:0:0::/root:/bin/sh\neve:x:1001:100::{}:/bin/sh\n", home.display())).unwrap();
+        fs::write(home.join(".config/user-dirs.dirs"), "XDG_MUSIC_DIR=\"$HOME/Musik\"\nXDG_VIDEOS_DIR=\"Videos\"\n").unwrap();
+        let lookup = |key: &str| match key {
+            "XDG_CACHE_HOME" => Some(String::from("")),
+            "XDG_CONFIG_HOME" => Some(String::from("relative")),
+            "XDG_DATA_HOME" => Some(String::from("/srv/data")),
+            "XDG_MUSIC_DIR" => Some(String::from("/srv/music")),
+            "XDG_DOWNLOAD_DIR" => Some(String::from("/srv/downloads")),
+            _ => None,
+        };
+        let explain = |kind| explain_with(kind, &lookup, &PasswdDatabase::File(passwd.clone()), 1001);
+
+        let home_dir = explain(DirectoryKind::Home);
+        assert_eq!(home_dir.path(), Some(home.as_path()));
+        assert_eq!(home_dir.source(), Some(&Source::File { path: passwd.clone(), line: 2 }));
+
+        let cache_dir = explain(DirectoryKind::Cache);
+        assert_eq!(cache_dir.path(), Some(home.join(".cache").as_path()));
+        assert_eq!(cache_dir.source(), Some(&Source::Default));
+        assert_eq!(cache_dir.rejected()[0].reason(), RejectionReason::Empty);
+
+        let config_dir = explain(DirectoryKind::Config);
+        assert_eq!(config_dir.rejected()[0].source(), &Source::EnvVar(String::from("XDG_CONFIG_HOME")));
+        assert_eq!(config_dir.rejected()[0].reason(), RejectionReason::NotAbsolute);
+
+        let data_dir = explain(DirectoryKind::Data);
+        assert_eq!(data_dir.path(), Some(Path::new("/srv/data")));
+        assert_eq!(data_dir.source(), Some(&Source::EnvVar(String::from("XDG_DATA_HOME"))));
+        assert!(data_dir.rejected().is_empty());
+
+        let audio_dir = explain(DirectoryKind::Audio);
+        assert_eq!(audio_dir.path(), Some(home.join("Musik").as_path()));
+        let user_dirs = home.join(".config/user-dirs.dirs");
+        assert_eq!(audio_dir.source(), Some(&Source::File { path: user_dirs.clone(), line: 1 }));
+
+        let download_dir = explain(DirectoryKind::Download);
+        assert_eq!(download_dir.path(), Some(Path::new("/srv/downloads")));
+        assert_eq!(download_dir.source(), Some(&Source::EnvVar(String::from("XDG_DOWNLOAD_DIR"))));
+
+        let video_dir = explain(DirectoryKind::Video);
+        assert_eq!(video_dir.path(), Some(home.as_path()));
+        assert_eq!(video_dir.source(), Some(&Source::Default));
+        assert_eq!(video_dir.rejected()[0].source(), &Source::File { path: user_dirs, line: 2 });
+
+        assert_eq!(explain(DirectoryKind::Runtime).path(), None);
+
+        fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -1,69 +1,127 @@
 use std::env;
+use std::path::PathBuf;
 
+use layout::Platform;
+use layout::PlatformBackend;
+use layout::PlatformFolders;
+use BaseDirBackend;
 use BaseDirectories;
 use ProjectDirectories;
+use xdg::XDG_BASE_DIR_VARIABLES;
 
-#[cfg(target_os = "macos")]
-pub fn base_directories() -> BaseDirectories {
-    let home_dir       = env::home_dir().unwrap();
-    let cache_dir      = home_dir.join("Library/Caches");
-    let config_dir     = home_dir.join("Library/Preferences");
-    let data_dir       = home_dir.join("Library/Application Support");
-    let data_local_dir = data_dir.clone();
-    let audio_dir      = home_dir.join("Music");
-    let desktop_dir    = home_dir.join("Desktop");
-    let document_dir   = home_dir.join("Documents");
-    let download_dir   = home_dir.join("Downloads");
-    let picture_dir    = home_dir.join("Pictures");
-    let public_dir     = home_dir.join("Public");
-    let video_dir      = home_dir.join("Movies");
-    let font_dir       = home_dir.join("Library/Fonts");
-
-    BaseDirectories {
-        home_dir:         home_dir,
-        cache_dir:        cache_dir,
-        config_dir:       config_dir,
-        data_dir:         data_dir,
-        data_local_dir:   data_local_dir,
-        executable_dir:   None,
-        runtime_dir:      None,
-
-        audio_dir:        audio_dir,
-        desktop_dir:      desktop_dir,
-        document_dir:     document_dir,
-        download_dir:     download_dir,
-        font_dir:         Some(font_dir),
-        picture_dir:      picture_dir,
-        public_dir:       public_dir,
-        template_dir:     None,
-        video_dir:        video_dir
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OsBackend;
+
+impl BaseDirBackend for OsBackend {
+    fn home_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).home_dir(dirs)
+    }
+
+    fn cache_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).cache_dir(dirs)
+    }
+
+    fn config_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).config_dir(dirs)
+    }
+
+    fn preference_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        backend(dirs).preference_dir(dirs)
+    }
+
+    fn data_roaming_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).data_roaming_dir(dirs)
+    }
+
+    fn data_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).data_dir(dirs)
+    }
+
+    fn executable_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        backend(dirs).executable_dir(dirs)
+    }
+
+    fn runtime_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        backend(dirs).runtime_dir(dirs)
     }
+
+    fn audio_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).audio_dir(dirs)
+    }
+
+    fn desktop_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).desktop_dir(dirs)
+    }
+
+    fn document_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).document_dir(dirs)
+    }
+
+    fn download_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).download_dir(dirs)
+    }
+
+    fn font_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        backend(dirs).font_dir(dirs)
+    }
+
+    fn picture_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).picture_dir(dirs)
+    }
+
+    fn public_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).public_dir(dirs)
+    }
+
+    fn template_dir(&self, dirs: &BaseDirectories) -> Option<PathBuf> {
+        backend(dirs).template_dir(dirs)
+    }
+
+    fn video_dir(&self, dirs: &BaseDirectories) -> PathBuf {
+        backend(dirs).video_dir(dirs)
+    }
+}
+
+/// Returns the inputs of the macOS layout, taking the home directory from `$HOME`.
+///
+/// The XDG variables are included for `LayoutPolicy::Xdg`.
+pub fn folders(dirs: &BaseDirectories) -> PlatformFolders {
+    #[allow(deprecated)]
+    let home_dir = dirs
+        .env_var("HOME")
+        .map(PathBuf::from)
+        .filter(|home| home.is_absolute())
+        .or_else(env::home_dir)
+        .expect("$HOME is unset and no home directory could be determined");
+    XDG_BASE_DIR_VARIABLES
+        .iter()
+        .fold(PlatformFolders::new(home_dir), |folders, &key| match dirs.env_var(key) {
+            Some(value) => folders.with_var(key, &value),
+            None => folders,
+        })
+}
+
+fn backend(dirs: &BaseDirectories) -> PlatformBackend {
+    PlatformBackend::new(Platform::MacOs, folders(dirs))
 }
 
 impl ProjectDirectories {
     pub fn from_unprocessed_string(value: &str) -> ProjectDirectories {
-        let project_name           = String::from(value);
-        let home_dir               = env::home_dir().unwrap();
-        let project_cache_dir      = home_dir.join("Library/Caches").join(&value);
-        let project_config_dir     = home_dir.join("Library/Preferences").join(&value);
-        let project_data_dir       = home_dir.join("Library/Application Support").join(&value);
-        let project_data_local_dir = project_data_dir.clone();
-
-        ProjectDirectories {
-            project_name:           project_name,
-            project_cache_dir:      project_cache_dir,
-            project_config_dir:     project_config_dir,
-            project_data_dir:       project_data_dir,
-            project_data_local_dir: project_data_local_dir,
-            project_runtime_dir:    None,
-        }
+        let folders = folders(&BaseDirectories::new());
+        Platform::MacOs.project_dirs_from_unprocessed_string(value, &folders)
     }
 
     pub fn from_project_name(project_name: &str) -> ProjectDirectories {
-        ProjectDirectories::from_unprocessed_string(project_name)
+        let folders = folders(&BaseDirectories::new());
+        Platform::MacOs.project_dirs_from_project_name(project_name, &folders)
     }
 
     pub fn from_qualified_project_name(qualified_project_name: &str) -> ProjectDirectories {
-        ProjectDirectories::from_unprocessed_string(qualified_project_name)
+        let folders = folders(&BaseDirectories::new());
+        Platform::MacOs.project_dirs_from_qualified_project_name(qualified_project_name, &folders)
+    }
+
+    pub fn from_qualified_project_name_nested(qualified_project_name: &str) -> ProjectDirectories {
+        ProjectDirectories::from_qualified_project_name(qualified_project_name)
     }
 }
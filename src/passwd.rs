@@ -0,0 +1,274 @@
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::ffi::OsStr;
+#[cfg(test)]
+use std::fs::File;
+use std::io;
+#[cfg(test)]
+use std::io::BufRead;
+#[cfg(test)]
+use std::io::BufReader;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::ptr;
+
+use libc;
+
+use home::HomeDirError;
+use provenance::Rejected;
+use provenance::RejectionReason;
+use provenance::Source;
+use xdg::env_dir;
+
+/// A single entry of a password database in the format of `passwd(5)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswdEntry {
+    name: String,
+    uid: u32,
+    gid: u32,
+    home_dir: PathBuf,
+}
+
+/// The password database entries are looked up in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswdDatabase {
+    /// The database of the system, queried with `getpwuid_r(3)` and `getpwnam_r(3)`,
+    /// which includes users of directory services like LDAP, sssd or systemd-homed.
+    System,
+    /// A file in the format of `passwd(5)`, standing in for the database in tests.
+    #[cfg(test)]
+    File(PathBuf),
+}
+
+#[deny(missing_docs)]
+impl PasswdEntry {
+    /// Parses a line of the form `name:password:uid:gid:gecos:home:shell`.
+    ///
+    /// Returns `None` for comments, blank lines and malformed entries.
+    pub fn parse(line: &str) -> Option<PasswdEntry> {
+        if line.starts_with('#') {
+            return None;
+        }
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() != 7 || fields[0].is_empty() {
+            return None;
+        }
+        Some(PasswdEntry {
+            name: String::from(fields[0]),
+            uid: fields[2].parse().ok()?,
+            gid: fields[3].parse().ok()?,
+            home_dir: PathBuf::from(fields[5]),
+        })
+    }
+
+    /// Returns the user name.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the numerical user id.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the numerical id of the primary group.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Returns the home directory, exactly as listed in the database.
+    pub fn home_dir(&self) -> &Path {
+        self.home_dir.as_path()
+    }
+}
+
+impl PasswdDatabase {
+    /// Returns the entry with the given uid, together with where it has been found.
+    pub fn find_uid(&self, uid: u32) -> io::Result<Option<(PasswdEntry, Source)>> {
+        match *self {
+            PasswdDatabase::System => {
+                let entry = query(|passwd, buf, len, result| unsafe { libc::getpwuid_r(uid, passwd, buf, len, result) })?;
+                Ok(entry.map(|entry| (entry, Source::Platform)))
+            }
+            #[cfg(test)]
+            PasswdDatabase::File(ref path) => find_entry_source(path, |entry| entry.uid == uid),
+        }
+    }
+
+    /// Returns the entry with the given user name, together with where it has been found.
+    pub fn find_name(&self, name: &str) -> io::Result<Option<(PasswdEntry, Source)>> {
+        match *self {
+            PasswdDatabase::System => {
+                let c_name = match CString::new(name) {
+                    Ok(c_name) => c_name,
+                    Err(_) => return Ok(None),
+                };
+                let entry = query(|passwd, buf, len, result| unsafe {
+                    libc::getpwnam_r(c_name.as_ptr(), passwd, buf, len, result)
+                })?;
+                Ok(entry.map(|entry| (entry, Source::Platform)))
+            }
+            #[cfg(test)]
+            PasswdDatabase::File(ref path) => find_entry_source(path, |entry| entry.name == name),
+        }
+    }
+}
+
+/// Runs one of the reentrant `getpw*_r` functions, growing the buffer for the
+/// strings of the entry until they fit.
+fn query<F>(getpw: F) -> io::Result<Option<PasswdEntry>>
+where
+    F: Fn(*mut libc::passwd, *mut libc::c_char, libc::size_t, *mut *mut libc::passwd) -> libc::c_int,
+{
+    let mut buf: Vec<libc::c_char> = vec![0; 1024];
+    let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+    let mut result: *mut libc::passwd = ptr::null_mut();
+    loop {
+        match getpw(&mut passwd, buf.as_mut_ptr(), buf.len(), &mut result) {
+            0 => break,
+            libc::ERANGE => {
+                let len = buf.len() * 2;
+                buf.resize(len, 0);
+            }
+            // some implementations report a missing entry as an error
+            libc::ENOENT | libc::ESRCH => return Ok(None),
+            code => return Err(io::Error::from_raw_os_error(code)),
+        }
+    }
+    if result.is_null() {
+        return Ok(None);
+    }
+    // the strings of the entry point into `buf`, so they have to be copied before it is dropped
+    let (name, home_dir) = unsafe { (c_bytes(passwd.pw_name), c_bytes(passwd.pw_dir)) };
+    Ok(Some(PasswdEntry {
+        name: String::from_utf8_lossy(name).into_owned(),
+        uid: passwd.pw_uid,
+        gid: passwd.pw_gid,
+        home_dir: PathBuf::from(OsStr::from_bytes(home_dir)),
+    }))
+}
+
+/// Returns the bytes of a C string, or nothing for a null pointer.
+unsafe fn c_bytes<'a>(ptr: *const libc::c_char) -> &'a [u8] {
+    if ptr.is_null() {
+        &[]
+    } else {
+        CStr::from_ptr(ptr).to_bytes()
+    }
+}
+
+/// Returns the first entry of the password database file at `passwd_path` matching
+/// the predicate, together with the line it has been found in, counted from 1.
+#[cfg(test)]
+fn find_entry_source<F: Fn(&PasswdEntry) -> bool>(passwd_path: &Path, predicate: F) -> io::Result<Option<(PasswdEntry, Source)>> {
+    let reader = BufReader::new(File::open(passwd_path)?);
+    for (index, line) in reader.lines().enumerate() {
+        if let Some(entry) = PasswdEntry::parse(&line?) {
+            if predicate(&entry) {
+                let source = Source::File { path: passwd_path.to_path_buf(), line: index + 1 };
+                return Ok(Some((entry, source)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns `$HOME`, as read through `lookup`, if it is an absolute path, and the
+/// home directory of the entry for `uid` in `database` otherwise.
+pub fn home_dir_from<F: Fn(&str) -> Option<String>>(lookup: &F, database: &PasswdDatabase, uid: u32) -> Result<PathBuf, HomeDirError> {
+    home_dir_with(lookup, database, uid, &mut Vec::new()).map(|(home_dir, _)| home_dir)
+}
+
+/// Like `home_dir_from`, but also returns where the home directory came from
+/// and adds the ignored values to `rejected`.
+pub fn home_dir_with<F: Fn(&str) -> Option<String>>(
+    lookup: &F,
+    database: &PasswdDatabase,
+    uid: u32,
+    rejected: &mut Vec<Rejected>,
+) -> Result<(PathBuf, Source), HomeDirError> {
+    if let Some(home_dir) = env_dir("HOME", lookup, rejected) {
+        return Ok((home_dir, Source::EnvVar(String::from("HOME"))));
+    }
+    match database.find_uid(uid) {
+        Ok(Some((entry, source))) => {
+            if entry.home_dir.is_absolute() {
+                return Ok((entry.home_dir, source));
+            }
+            let value = entry.home_dir.to_string_lossy().into_owned();
+            rejected.push(Rejected::new(source, &value, RejectionReason::NotAbsolute));
+            Err(HomeDirError::NotAbsolute(entry.home_dir))
+        }
+        Ok(None) => Err(HomeDirError::NoEntry(uid)),
+        Err(err) => Err(HomeDirError::Io(err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use home::HomeDirError;
+    use passwd::home_dir_from;
+    use passwd::PasswdDatabase;
+    use passwd::PasswdEntry;
+
+    #[test]
+    fn test_passwd_entry_parse() {
+        let actual1 = PasswdEntry::parse("eve:x:1001:100:Eve,,,:/home/eve:/bin/bash").unwrap();
+        assert_eq!(actual1.name(), "eve");
+        assert_eq!(actual1.uid(), 1001);
+        assert_eq!(actual1.gid(), 100);
+        assert_eq!(actual1.home_dir(), PathBuf::from("/home/eve"));
+
+        assert_eq!(PasswdEntry::parse("# comment"), None);
+        assert_eq!(PasswdEntry::parse(""), None);
+        assert_eq!(PasswdEntry::parse("eve:x:abc:100::/home/eve:/bin/sh"), None);
+    }
+
+    #[test]
+    fn test_home_dir_from() {
+        let passwd = env::temp_dir().join(format!("directories-passwd-{}", ::std::process::id()));
+        fs::write(&passwd, "root:x:0:0:root:/root:/bin/sh\nbroken:x:1002:1002::relative:/bin/sh\n").unwrap();
+
+        let database = &PasswdDatabase::File(passwd.clone());
+        let home = |value: &'static str| move |key: &str| if key == "HOME" { Some(String::from(value)) } else { None };
+        let unset = |_: &str| None;
+
+        let actual1 = home_dir_from(&home("/home/eve"), database, 0).unwrap();
+        assert_eq!(actual1, PathBuf::from("/home/eve"));
+
+        let actual2 = home_dir_from(&home(""), database, 0).unwrap();
+        assert_eq!(actual2, PathBuf::from("/root"));
+
+        let actual3 = home_dir_from(&unset, database, 0).unwrap();
+        assert_eq!(actual3, PathBuf::from("/root"));
+
+        match home_dir_from(&unset, database, 1001) {
+            Err(HomeDirError::NoEntry(1001)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match home_dir_from(&unset, database, 1002) {
+            Err(HomeDirError::NotAbsolute(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match home_dir_from(&unset, &PasswdDatabase::File(passwd.join("missing")), 0) {
+            Err(HomeDirError::Io(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        fs::remove_file(&passwd).unwrap();
+    }
+
+    #[test]
+    fn test_system_database() {
+        let (root, _) = PasswdDatabase::System.find_uid(0).unwrap().unwrap();
+        assert_eq!(root.name(), "root");
+        let (by_name, _) = PasswdDatabase::System.find_name("root").unwrap().unwrap();
+        assert_eq!(by_name.uid(), 0);
+        assert!(PasswdDatabase::System.find_name("no such user").unwrap().is_none());
+    }
+}
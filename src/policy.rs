@@ -0,0 +1,171 @@
+use std::path::Path;
+
+use layout::Platform;
+use layout::PlatformFolders;
+use ProjectDirectories;
+
+/// Which conventions `ProjectDirectories` follow on macOS.
+///
+/// Command-line tools are commonly expected to keep their configuration in
+/// `~/.config/tool` rather than in `~/Library/Preferences/tool`, which is meant
+/// for property lists. `Xdg` applies the rules used on Linux instead, including
+/// `$XDG_CONFIG_HOME` and the other XDG variables. On all other platforms, both
+/// policies produce the same directories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayoutPolicy {
+    /// The conventions of the platform, e. g. `~/Library/Preferences/` on macOS.
+    Native,
+    /// The XDG base directory specification, e. g. `~/.config/` on macOS.
+    Xdg,
+}
+
+/// Where `ProjectDirectories` keep configuration on macOS.
+///
+/// Apple reserves `~/Library/Preferences` for property lists managed by
+/// `NSUserDefaults`; configuration in other formats belongs into
+/// `~/Library/Application Support`. The property list directory is always
+/// available as `BaseDirectories::preference_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigDirPolicy {
+    /// `~/Library/Preferences`, the traditional choice of this library.
+    Preferences,
+    /// `~/Library/Application Support`, next to the data directory.
+    ApplicationSupport,
+}
+
+#[deny(missing_docs)]
+impl LayoutPolicy {
+    /// Returns the platform whose layout this policy applies on `platform`.
+    pub fn effective_platform(self, platform: Platform) -> Platform {
+        match (self, platform) {
+            (LayoutPolicy::Xdg, Platform::MacOs) => Platform::Linux,
+            _ => platform,
+        }
+    }
+
+    /// Determines which policy the existing configuration of a project follows.
+    ///
+    /// If the config directory of exactly one policy exists, that policy is
+    /// returned; otherwise, i. e. for new installations or if both exist, `default`.
+    pub fn detect(project_name: &str, default: LayoutPolicy) -> LayoutPolicy {
+        match current_folders() {
+            Some(folders) => {
+                LayoutPolicy::detect_with(Platform::current(), project_name, &folders, default, |dir| dir.is_dir())
+            }
+            None => default,
+        }
+    }
+
+    /// Like `detect`, but for simulated inputs, checking directories with `exists`.
+    pub fn detect_with<F: Fn(&Path) -> bool>(platform: Platform, project_name: &str, folders: &PlatformFolders, default: LayoutPolicy, exists: F) -> LayoutPolicy {
+        let config_exists = |policy: LayoutPolicy| {
+            let project_dirs = policy.project_dirs(platform, project_name, folders);
+            exists(project_dirs.project_config_dir())
+        };
+        match (config_exists(LayoutPolicy::Native), config_exists(LayoutPolicy::Xdg)) {
+            (true, false) => LayoutPolicy::Native,
+            (false, true) => LayoutPolicy::Xdg,
+            _ => default,
+        }
+    }
+
+    /// Computes the project directories this policy produces on `platform`, see
+    /// `ProjectDirectories::from_project_name_with_policy`.
+    pub fn project_dirs(self, platform: Platform, project_name: &str, folders: &PlatformFolders) -> ProjectDirectories {
+        self.effective_platform(platform)
+            .project_dirs_from_project_name(project_name, folders)
+    }
+}
+
+#[deny(missing_docs)]
+impl ProjectDirectories {
+    /// Like `from_project_name`, but following the given policy on macOS.
+    ///
+    /// With `LayoutPolicy::Xdg`, the project name is processed as on Linux,
+    /// e. g. `"Foo Bar"` becomes `~/.config/foo-bar/`.
+    pub fn from_project_name_with_policy(project_name: &str, policy: LayoutPolicy) -> ProjectDirectories {
+        match current_folders() {
+            Some(ref folders) if policy == LayoutPolicy::Xdg => {
+                policy.project_dirs(Platform::current(), project_name, folders)
+            }
+            _ => ProjectDirectories::from_project_name(project_name),
+        }
+    }
+
+    /// Like `from_qualified_project_name`, but following the given policy on macOS.
+    ///
+    /// With `LayoutPolicy::Xdg`, the name is processed as on Linux, e. g.
+    /// `"org.foo.Bar"` becomes `~/.config/bar/`.
+    pub fn from_qualified_project_name_with_policy(qualified_project_name: &str, policy: LayoutPolicy) -> ProjectDirectories {
+        match current_folders() {
+            Some(ref folders) if policy == LayoutPolicy::Xdg => policy
+                .effective_platform(Platform::current())
+                .project_dirs_from_qualified_project_name(qualified_project_name, folders),
+            _ => ProjectDirectories::from_qualified_project_name(qualified_project_name),
+        }
+    }
+
+    /// Like `from_project_name`, but keeping configuration where the given policy says on macOS.
+    pub fn from_project_name_with_config_dir_policy(project_name: &str, policy: ConfigDirPolicy) -> ProjectDirectories {
+        match current_folders() {
+            Some(folders) => {
+                let folders = folders.with_config_dir_policy(policy);
+                Platform::current().project_dirs_from_project_name(project_name, &folders)
+            }
+            None => ProjectDirectories::from_project_name(project_name),
+        }
+    }
+}
+
+/// Returns the inputs of the current platform's layout, if the policy makes a difference on it.
+#[cfg(target_os = "macos")]
+fn current_folders() -> Option<PlatformFolders> {
+    Some(::mac::folders(&::BaseDirectories::new()))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn current_folders() -> Option<PlatformFolders> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::path::PathBuf;
+    use kind::DirectoryKind;
+    use layout::Platform;
+    use layout::PlatformFolders;
+    use policy::ConfigDirPolicy;
+    use policy::LayoutPolicy;
+
+    #[test]
+    fn test_layout_policy() {
+        let folders = PlatformFolders::new("/Users/eve").with_var("XDG_CONFIG_HOME", "/Users/eve/dotfiles");
+        let actual1 = LayoutPolicy::Xdg.project_dirs(Platform::MacOs, "Foo Tool", &folders);
+        assert_eq!(actual1.project_config_dir(), Path::new("/Users/eve/dotfiles/foo-tool"));
+        assert_eq!(actual1.project_cache_dir(), Path::new("/Users/eve/.cache/foo-tool"));
+
+        let actual2 = LayoutPolicy::Native.project_dirs(Platform::MacOs, "Foo Tool", &folders);
+        assert_eq!(actual2.project_config_dir(), Path::new("/Users/eve/Library/Preferences/Foo Tool"));
+
+        let native = Path::new("/Users/eve/Library/Preferences/Foo Tool");
+        let xdg = Path::new("/Users/eve/dotfiles/foo-tool");
+        let detect = |existing: &[&Path]| {
+            LayoutPolicy::detect_with(Platform::MacOs, "Foo Tool", &folders, LayoutPolicy::Xdg, |dir| existing.contains(&dir))
+        };
+        assert_eq!(detect(&[]), LayoutPolicy::Xdg);
+        assert_eq!(detect(&[native]), LayoutPolicy::Native);
+        assert_eq!(detect(&[xdg]), LayoutPolicy::Xdg);
+        assert_eq!(detect(&[native, xdg]), LayoutPolicy::Xdg);
+    }
+
+    #[test]
+    fn test_config_dir_policy() {
+        let folders = PlatformFolders::new("/Users/eve").with_config_dir_policy(ConfigDirPolicy::ApplicationSupport);
+        let actual1 = Platform::MacOs.project_dirs_from_project_name("Foo", &folders);
+        assert_eq!(actual1.project_config_dir(), Path::new("/Users/eve/Library/Application Support/Foo"));
+        let actual2 = Platform::MacOs.base_dir(DirectoryKind::Preference, &folders);
+        assert_eq!(actual2, Some(PathBuf::from("/Users/eve/Library/Preferences")));
+        assert_eq!(Platform::Linux.base_dir(DirectoryKind::Preference, &folders), None);
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use kind::DirectoryKind;
+use BaseDirectories;
+
+/// Where the value of a directory came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// The environment variable with this name.
+    EnvVar(String),
+    /// A line of a file, e. g. `user-dirs.dirs`. Lines are counted from 1.
+    File { path: PathBuf, line: usize },
+    /// A built-in default, possibly derived from another directory.
+    Default,
+    /// A location the operating system provides, e. g. a known folder on Windows
+    /// or the home directory in the password database.
+    Platform,
+}
+
+/// Why a candidate value for a directory has been ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+    /// The value is empty, which the XDG specifications treat like an unset variable.
+    Empty,
+    /// The value is a relative path, which the XDG specifications require to be ignored.
+    NotAbsolute,
+    /// The value does not follow the required syntax, e. g. it is not quoted in `user-dirs.dirs`.
+    Malformed,
+}
+
+/// A candidate value for a directory that has been ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rejected {
+    source: Source,
+    value: String,
+    reason: RejectionReason,
+}
+
+/// How the directory of one `DirectoryKind` has been resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Explanation {
+    kind: DirectoryKind,
+    path: Option<PathBuf>,
+    source: Option<Source>,
+    rejected: Vec<Rejected>,
+}
+
+#[deny(missing_docs)]
+impl Rejected {
+    /// Creates a rejected candidate.
+    pub fn new(source: Source, value: &str, reason: RejectionReason) -> Rejected {
+        Rejected { source, value: String::from(value), reason }
+    }
+
+    /// Returns where the candidate came from.
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Returns the candidate value, exactly as found.
+    pub fn value(&self) -> &str {
+        self.value.as_str()
+    }
+
+    /// Returns why the candidate has been ignored.
+    pub fn reason(&self) -> RejectionReason {
+        self.reason
+    }
+}
+
+#[deny(missing_docs)]
+impl Explanation {
+    /// Creates an explanation for a resolved directory.
+    pub fn new(kind: DirectoryKind, path: Option<PathBuf>, source: Option<Source>, rejected: Vec<Rejected>) -> Explanation {
+        Explanation { kind, path, source, rejected }
+    }
+
+    /// Returns the kind of the directory.
+    pub fn kind(&self) -> DirectoryKind {
+        self.kind
+    }
+
+    /// Returns the resolved directory, or `None` if there is none.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns the resolved directory, consuming the explanation.
+    pub fn into_path(self) -> Option<PathBuf> {
+        self.path
+    }
+
+    /// Returns where the resolved directory came from, or `None` if there is none.
+    pub fn source(&self) -> Option<&Source> {
+        self.source.as_ref()
+    }
+
+    /// Returns the candidates that have been ignored, in the order they were considered.
+    pub fn rejected(&self) -> &[Rejected] {
+        &self.rejected
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::EnvVar(ref name) => write!(f, "${}", name),
+            Source::File { ref path, line } => write!(f, "{}:{}", path.display(), line),
+            Source::Default => f.write_str("built-in default"),
+            Source::Platform => f.write_str("platform"),
+        }
+    }
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            RejectionReason::Empty => "value is empty",
+            RejectionReason::NotAbsolute => "value is not an absolute path",
+            RejectionReason::Malformed => "value is malformed",
+        })
+    }
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ignored {:?} from {}: {}", self.value, self.source, self.reason)
+    }
+}
+
+impl BaseDirectories {
+    /// Explains where every directory comes from, in the order of `DirectoryKind::all()`.
+    ///
+    /// For each directory, the result contains its source (an environment variable,
+    /// a line of a file, or a built-in default) and every candidate value that has
+    /// been ignored along the way, together with the reason, e. g. a relative
+    /// `$XDG_CONFIG_HOME`.
+    pub fn explain(&self) -> Vec<Explanation> {
+        self.backend.explain_all(self)
+    }
+
+    /// Explains where the directory of the given kind comes from, see `explain`.
+    pub fn explain_kind(&self, kind: DirectoryKind) -> Explanation {
+        self.backend.explain(self, kind)
+    }
+}
@@ -0,0 +1,189 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use kind::DirectoryKind;
+use layout::project_dir_name;
+use layout::qualified_project_dir_name;
+use layout::Platform;
+use warnings;
+use BaseDirectories;
+use ProjectDirectories;
+use ServiceDirectories;
+
+/// The values of all base directories, resolved at one instant.
+///
+/// The functions of `BaseDirectories` consult the environment anew on every
+/// call, so a program whose environment changes while it runs may observe
+/// inconsistent values. A snapshot reads the environment once and keeps the
+/// result, and can be compared against a later snapshot to find out which
+/// directories have changed in the meantime.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DirectorySnapshot {
+    dirs: Vec<(DirectoryKind, Option<PathBuf>)>,
+    service_dirs: Option<ServiceDirectories>,
+}
+
+/// A directory whose value differs between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DirectoryChange {
+    kind: DirectoryKind,
+    before: Option<PathBuf>,
+    after: Option<PathBuf>,
+}
+
+#[deny(missing_docs)]
+impl DirectorySnapshot {
+    /// Resolves all base directories from the current environment.
+    pub fn new() -> DirectorySnapshot {
+        BaseDirectories::new().snapshot()
+    }
+
+    /// Returns the directory of the given kind, or `None` if the platform has none.
+    pub fn get(&self, kind: DirectoryKind) -> Option<&Path> {
+        self.dirs
+            .iter()
+            .find(|&&(dir_kind, _)| dir_kind == kind)
+            .and_then(|entry| entry.1.as_deref())
+    }
+
+    /// Returns all directories, in the order of `DirectoryKind::all()`.
+    pub fn iter(&self) -> impl Iterator<Item = (DirectoryKind, Option<&Path>)> {
+        self.dirs.iter().map(|&(kind, ref dir)| (kind, dir.as_deref()))
+    }
+
+    /// Returns the directories whose values differ in `later`, in the order of `DirectoryKind::all()`.
+    pub fn changes(&self, later: &DirectorySnapshot) -> Vec<DirectoryChange> {
+        DirectoryKind::all()
+            .filter_map(|kind| {
+                let before = self.get(kind);
+                let after = later.get(kind);
+                if before == after {
+                    return None;
+                }
+                Some(DirectoryChange {
+                    kind,
+                    before: before.map(Path::to_path_buf),
+                    after: after.map(Path::to_path_buf),
+                })
+            })
+            .collect()
+    }
+
+    /// Computes the directories `ProjectDirectories::from_unprocessed_string` produces,
+    /// from the directories of this snapshot and the service directories of the same instant.
+    pub fn project_dirs_from_unprocessed_string(&self, value: &str) -> ProjectDirectories {
+        let project_dir = |kind| {
+            let project_dir = match (Platform::current(), kind) {
+                // Windows keeps the cache inside the local data directory of the project
+                (Platform::Windows, DirectoryKind::Cache) => self.get(DirectoryKind::Data)?.join(value).join("cache"),
+                _ => self.get(kind)?.join(value),
+            };
+            Some(project_dir)
+        };
+        ProjectDirectories::with_service_dirs(value, self.service_dirs.clone(), project_dir)
+    }
+
+    /// Computes the directories `ProjectDirectories::from_project_name` produces, see
+    /// `project_dirs_from_unprocessed_string`.
+    pub fn project_dirs_from_project_name(&self, project_name: &str) -> ProjectDirectories {
+        self.project_dirs_from_unprocessed_string(&project_dir_name(Platform::current(), project_name))
+    }
+
+    /// Computes the directories `ProjectDirectories::from_qualified_project_name` produces, see
+    /// `project_dirs_from_unprocessed_string`.
+    pub fn project_dirs_from_qualified_project_name(&self, qualified_project_name: &str) -> ProjectDirectories {
+        let name = qualified_project_dir_name(Platform::current(), qualified_project_name);
+        self.project_dirs_from_unprocessed_string(&name)
+    }
+}
+
+impl Default for DirectorySnapshot {
+    fn default() -> DirectorySnapshot {
+        DirectorySnapshot::new()
+    }
+}
+
+impl BaseDirectories {
+    /// Resolves all directories at once, see `DirectorySnapshot`.
+    pub fn snapshot(&self) -> DirectorySnapshot {
+        let dirs = self
+            .explain()
+            .into_iter()
+            .map(|explanation| {
+                warnings::report(self, explanation.rejected());
+                (explanation.kind(), explanation.into_path())
+            })
+            .collect();
+        let service_dirs = ServiceDirectories::from_lookup(|key| self.env_var(key));
+        DirectorySnapshot { dirs, service_dirs }
+    }
+}
+
+#[deny(missing_docs)]
+impl DirectoryChange {
+    /// Returns the kind of the directory that has changed.
+    pub fn kind(&self) -> DirectoryKind {
+        self.kind
+    }
+
+    /// Returns the value in the earlier snapshot.
+    pub fn before(&self) -> Option<&Path> {
+        self.before.as_deref()
+    }
+
+    /// Returns the value in the later snapshot.
+    pub fn after(&self) -> Option<&Path> {
+        self.after.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::path::PathBuf;
+    use kind::DirectoryKind;
+    use snapshot::DirectorySnapshot;
+    use BaseDirectories;
+
+    #[test]
+    fn test_directory_snapshot_changes() {
+        let earlier = DirectorySnapshot {
+            dirs: vec![
+                (DirectoryKind::Home, Some(PathBuf::from("/home/eve"))),
+                (DirectoryKind::Cache, Some(PathBuf::from("/home/eve/.cache"))),
+                (DirectoryKind::Runtime, None),
+            ],
+            service_dirs: None,
+        };
+        let later = DirectorySnapshot {
+            dirs: vec![
+                (DirectoryKind::Home, Some(PathBuf::from("/home/eve"))),
+                (DirectoryKind::Cache, Some(PathBuf::from("/var/tmp/cache"))),
+                (DirectoryKind::Runtime, Some(PathBuf::from("/run/user/1001"))),
+            ],
+            service_dirs: None,
+        };
+
+        assert!(earlier.changes(&earlier.clone()).is_empty());
+
+        let changes = earlier.changes(&later);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].kind(), DirectoryKind::Cache);
+        assert_eq!(changes[0].before(), Some(Path::new("/home/eve/.cache")));
+        assert_eq!(changes[0].after(), Some(Path::new("/var/tmp/cache")));
+        assert_eq!(changes[1].kind(), DirectoryKind::Runtime);
+        assert_eq!(changes[1].before(), None);
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_snapshot_project_dirs() {
+        let dirs = BaseDirectories::from_env(vec![("HOME", "/home/eve"), ("STATE_DIRECTORY", "/var/lib/foo")]);
+        let snapshot = dirs.snapshot();
+        let project_dirs = snapshot.project_dirs_from_project_name("Foo App");
+        assert_eq!(project_dirs.project_name(), "foo-app");
+        assert_eq!(project_dirs.project_cache_dir(), Path::new("/home/eve/.cache/foo-app"));
+        assert_eq!(project_dirs.project_data_dir(), Path::new("/var/lib/foo"));
+        assert!(project_dirs.service_directories().is_some());
+    }
+}
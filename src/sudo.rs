@@ -0,0 +1,231 @@
+use std::env;
+use std::ffi::CString;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::mem;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::io::FromRawFd;
+use std::path::Component;
+use std::path::Path;
+
+use libc;
+
+use user::UserDirectories;
+use user::UserLookupError;
+use ProjectDirectories;
+
+/// Whether `ProjectDirectories` follow the user who invoked `sudo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SudoPolicy {
+    /// Use the environment of the current process as is.
+    Ignore,
+    /// If running as root via `sudo`, use the directories of the invoking user.
+    InvokingUser,
+}
+
+/// The user who invoked `sudo`, as described by `$SUDO_USER`, `$SUDO_UID` and `$SUDO_GID`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SudoUser {
+    name: String,
+    uid: u32,
+    gid: Option<u32>,
+}
+
+#[deny(missing_docs)]
+impl SudoUser {
+    /// Returns the invoking user if the current process runs as root via `sudo`.
+    ///
+    /// Returns `None` if the effective uid is not root, if the variables are
+    /// missing or malformed, or if root itself invoked `sudo`.
+    pub fn from_env() -> Option<SudoUser> {
+        if unsafe { libc::geteuid() } != 0 {
+            return None;
+        }
+        SudoUser::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Reads the invoking user through the given variable lookup.
+    pub fn from_lookup<F: Fn(&str) -> Option<String>>(lookup: F) -> Option<SudoUser> {
+        let name = lookup("SUDO_USER").filter(|name| !name.is_empty())?;
+        let uid = lookup("SUDO_UID")?.parse().ok().filter(|&uid| uid != 0)?;
+        let gid = lookup("SUDO_GID").and_then(|gid| gid.parse().ok());
+        Some(SudoUser { name, uid, gid })
+    }
+
+    /// Returns the name of the invoking user.
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the uid of the invoking user.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the base directories of the invoking user.
+    pub fn user_directories(&self) -> Result<UserDirectories, UserLookupError> {
+        UserDirectories::from_uid(self.uid)
+    }
+
+    /// Creates `path` and all of its missing parents, like `fs::create_dir_all`,
+    /// and hands every directory created by this call over to the invoking user.
+    ///
+    /// Directories that existed before are left untouched. If `$SUDO_GID` was not
+    /// provided, the primary group of the invoking user is used.
+    ///
+    /// The invoking user may own the parent directories, so each directory is
+    /// created and handed over through a handle relative to its parent, which
+    /// does not follow symbolic links. If an entry is replaced while this
+    /// function runs, it fails instead of changing the owner of the replacement.
+    pub fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let gid = match self.gid {
+            Some(gid) => gid,
+            None => self
+                .user_directories()
+                .map(|user_dirs| user_dirs.user().gid())
+                .map_err(|err| io::Error::new(io::ErrorKind::NotFound, err.to_string()))?,
+        };
+        // the ancestors of a relative path end in an empty path, i. e. the working directory
+        let existing = path
+            .ancestors()
+            .find(|dir| dir.as_os_str().is_empty() || dir.is_dir())
+            .unwrap_or(path);
+        let mut parent = if existing.as_os_str().is_empty() { open_dir(Path::new("."))? } else { open_dir(existing)? };
+        for component in path.strip_prefix(existing).unwrap_or(path).components() {
+            let name = match component {
+                Component::Normal(name) => c_string(name)?,
+                _ => {
+                    let message = format!("cannot create {}: unexpected component {:?}", path.display(), component);
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+                }
+            };
+            let created = unsafe { libc::mkdirat(parent.as_raw_fd(), name.as_ptr(), 0o777) } == 0;
+            if !created {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::AlreadyExists {
+                    return Err(err);
+                }
+            }
+            let dir = open_dir_at(&parent, &name)?;
+            if created {
+                let mut stat: libc::stat = unsafe { mem::zeroed() };
+                if unsafe { libc::fstat(dir.as_raw_fd(), &mut stat) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if stat.st_uid != unsafe { libc::geteuid() } {
+                    let message = format!("cannot create {}: {:?} has been replaced", path.display(), name);
+                    return Err(io::Error::new(io::ErrorKind::PermissionDenied, message));
+                }
+                if unsafe { libc::fchown(dir.as_raw_fd(), self.uid, gid) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            parent = dir;
+        }
+        Ok(())
+    }
+}
+
+/// Opens the existing directory at `path`, following symbolic links.
+fn open_dir(path: &Path) -> io::Result<File> {
+    OpenOptions::new().read(true).custom_flags(libc::O_DIRECTORY).open(path)
+}
+
+/// Opens the directory `name` inside `parent`, failing if it is a symbolic link or no directory.
+fn open_dir_at(parent: &File, name: &CString) -> io::Result<File> {
+    let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+    let fd = unsafe { libc::openat(parent.as_raw_fd(), name.as_ptr(), flags) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+fn c_string(name: &OsStr) -> io::Result<CString> {
+    CString::new(name.as_bytes()).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
+
+impl ProjectDirectories {
+    /// Computes the project directories according to the given `SudoPolicy`.
+    ///
+    /// With `SudoPolicy::InvokingUser`, a process running as root via `sudo`
+    /// gets the directories of the invoking user, regardless of whether `$HOME`
+    /// points to that user's home or to `/root`. Directories created for that
+    /// user should be created with `SudoUser::create_dir_all`.
+    pub fn from_project_name_with_sudo_policy(project_name: &str, policy: SudoPolicy) -> Result<ProjectDirectories, UserLookupError> {
+        let sudo_user = match policy {
+            SudoPolicy::Ignore => None,
+            SudoPolicy::InvokingUser => SudoUser::from_env(),
+        };
+        match sudo_user {
+            Some(sudo_user) => Ok(ProjectDirectories::from_project_name_for_user(
+                project_name,
+                &sudo_user.user_directories()?,
+            )),
+            None => Ok(ProjectDirectories::from_project_name(project_name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::os::unix::fs as unix_fs;
+    use std::os::unix::fs::MetadataExt;
+    use libc;
+    use sudo::SudoUser;
+
+    #[test]
+    fn test_sudo_user_from_lookup() {
+        let actual1 = SudoUser::from_lookup(|key| match key {
+            "SUDO_USER" => Some(String::from("eve")),
+            "SUDO_UID" => Some(String::from("1001")),
+            "SUDO_GID" => Some(String::from("100")),
+            _ => None,
+        }).unwrap();
+        assert_eq!(actual1.name(), "eve");
+        assert_eq!(actual1.uid(), 1001);
+        assert_eq!(actual1.gid, Some(100));
+
+        let actual2 = SudoUser::from_lookup(|key| match key {
+            "SUDO_USER" => Some(String::from("root")),
+            "SUDO_UID" => Some(String::from("0")),
+            _ => None,
+        });
+        assert_eq!(actual2, None);
+
+        let actual3 = SudoUser::from_lookup(|key| match key {
+            "SUDO_USER" => Some(String::from("eve")),
+            _ => None,
+        });
+        assert_eq!(actual3, None);
+    }
+
+    #[test]
+    fn test_sudo_user_create_dir_all() {
+        // handing directories over to another user requires root
+        if unsafe { libc::geteuid() } != 0 {
+            return;
+        }
+        let root = env::temp_dir().join(format!("directories-sudo-{}", ::std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let sudo_user = SudoUser { name: String::from("eve"), uid: 1001, gid: Some(100) };
+
+        sudo_user.create_dir_all(&root.join("a/b")).unwrap();
+        assert_eq!(fs::metadata(&root).unwrap().uid(), 0);
+        let created = fs::metadata(root.join("a/b")).unwrap();
+        assert_eq!((created.uid(), created.gid()), (1001, 100));
+        assert_eq!(fs::metadata(root.join("a")).unwrap().uid(), 1001);
+
+        unix_fs::symlink(root.join("target"), root.join("link")).unwrap();
+        assert!(sudo_user.create_dir_all(&root.join("link/c")).is_err());
+        assert!(!root.join("target").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,297 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use libc;
+
+use ProjectDirectories;
+use trim_and_replace_spaces_with_hyphens_then_lowercase;
+use strip_qualification;
+
+/// The configuration of the shadow password suite, which defines `UID_MIN` and `UID_MAX`.
+const LOGIN_DEFS_PATH: &str = "/etc/login.defs";
+
+/// The lowest uid handed out to regular users if `login.defs(5)` does not define `UID_MIN`.
+const DEFAULT_UID_MIN: u32 = 1000;
+
+/// The highest uid handed out to regular users if `login.defs(5)` does not define `UID_MAX`.
+const DEFAULT_UID_MAX: u32 = 60000;
+
+/// System-wide directories for daemons and services, laid out according to
+/// the Filesystem Hierarchy Standard.
+///
+/// The install prefix decides where configuration and variable data go:
+/// the distribution prefixes `/usr` and `/` use `/etc`, `/var` and `/run`,
+/// every other prefix (e.g. `/usr/local` or `/opt/app`) keeps them below
+/// itself, like autoconf's `sysconfdir` and `localstatedir` do.
+#[derive(Debug, Clone)]
+pub struct SystemDirectories {
+    prefix: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemProjectDirectories {
+    project_name: String,
+
+    project_cache_dir: PathBuf,
+    project_config_dir: PathBuf,
+    project_log_dir: PathBuf,
+    project_runtime_dir: PathBuf,
+    project_state_dir: PathBuf,
+}
+
+#[deny(missing_docs)]
+impl SystemDirectories {
+    /// Returns the system directories for the distribution prefix `/usr`.
+    pub fn new() -> SystemDirectories {
+        SystemDirectories::with_prefix("/usr")
+    }
+
+    /// Returns the system directories for the given install prefix.
+    pub fn with_prefix<P: Into<PathBuf>>(prefix: P) -> SystemDirectories {
+        SystemDirectories { prefix: prefix.into() }
+    }
+
+    /// Returns the install prefix these directories are derived from.
+    pub fn prefix(&self) -> &Path {
+        self.prefix.as_path()
+    }
+
+    /// Returns `true` if the effective uid of the current process belongs to root
+    /// or a system user, i. e. lies outside of the range from `UID_MIN` to `UID_MAX`
+    /// of `/etc/login.defs`, by default 1000 to 60000.
+    ///
+    /// Uids above `UID_MAX` include `nobody` (65534), which daemons commonly run as.
+    pub fn is_system_user() -> bool {
+        let uid = unsafe { libc::geteuid() };
+        let login_defs = fs::read_to_string(LOGIN_DEFS_PATH).unwrap_or_default();
+        is_system_uid(uid, &login_defs)
+    }
+
+    /// Returns the path to the system-wide cache directory.
+    ///
+    /// |Prefix        | Value                 | Example                |
+    /// | ------------ | --------------------- | ---------------------- |
+    /// | `/usr`       | `/var/cache/`         | /var/cache/            |
+    /// | other        | `$prefix/var/cache/`  | /usr/local/var/cache/  |
+    pub fn cache_dir(&self) -> PathBuf {
+        self.local_state_dir().join("cache")
+    }
+
+    /// Returns the path to the system-wide config directory.
+    ///
+    /// |Prefix        | Value                 | Example                |
+    /// | ------------ | --------------------- | ---------------------- |
+    /// | `/usr`       | `/etc/`               | /etc/                  |
+    /// | other        | `$prefix/etc/`        | /usr/local/etc/        |
+    pub fn config_dir(&self) -> PathBuf {
+        if self.is_distribution_prefix() {
+            PathBuf::from("/etc")
+        } else {
+            self.prefix.join("etc")
+        }
+    }
+
+    /// Returns the path to the system-wide log directory.
+    ///
+    /// |Prefix        | Value                 | Example                |
+    /// | ------------ | --------------------- | ---------------------- |
+    /// | `/usr`       | `/var/log/`           | /var/log/              |
+    /// | other        | `$prefix/var/log/`    | /usr/local/var/log/    |
+    pub fn log_dir(&self) -> PathBuf {
+        self.local_state_dir().join("log")
+    }
+
+    /// Returns the path to the system-wide runtime directory.
+    ///
+    /// |Prefix        | Value                 | Example                |
+    /// | ------------ | --------------------- | ---------------------- |
+    /// | `/usr`       | `/run/`               | /run/                  |
+    /// | other        | `$prefix/var/run/`    | /usr/local/var/run/    |
+    pub fn runtime_dir(&self) -> PathBuf {
+        if self.is_distribution_prefix() {
+            PathBuf::from("/run")
+        } else {
+            self.local_state_dir().join("run")
+        }
+    }
+
+    /// Returns the path to the system-wide state directory.
+    ///
+    /// |Prefix        | Value                 | Example                |
+    /// | ------------ | --------------------- | ---------------------- |
+    /// | `/usr`       | `/var/lib/`           | /var/lib/              |
+    /// | other        | `$prefix/var/lib/`    | /usr/local/var/lib/    |
+    pub fn state_dir(&self) -> PathBuf {
+        self.local_state_dir().join("lib")
+    }
+
+    fn is_distribution_prefix(&self) -> bool {
+        self.prefix == Path::new("/usr") || self.prefix == Path::new("/")
+    }
+
+    fn local_state_dir(&self) -> PathBuf {
+        if self.is_distribution_prefix() {
+            PathBuf::from("/var")
+        } else {
+            self.prefix.join("var")
+        }
+    }
+}
+
+impl Default for SystemDirectories {
+    fn default() -> SystemDirectories {
+        SystemDirectories::new()
+    }
+}
+
+impl SystemProjectDirectories {
+    pub fn from_unprocessed_string(value: &str, system_dirs: &SystemDirectories) -> SystemProjectDirectories {
+        SystemProjectDirectories {
+            project_name: String::from(value),
+            project_cache_dir: system_dirs.cache_dir().join(value),
+            project_config_dir: system_dirs.config_dir().join(value),
+            project_log_dir: system_dirs.log_dir().join(value),
+            project_runtime_dir: system_dirs.runtime_dir().join(value),
+            project_state_dir: system_dirs.state_dir().join(value),
+        }
+    }
+
+    pub fn from_project_name(project_name: &str) -> SystemProjectDirectories {
+        SystemProjectDirectories::from_project_name_with_prefix(project_name, "/usr")
+    }
+
+    pub fn from_project_name_with_prefix<P: Into<PathBuf>>(project_name: &str, prefix: P) -> SystemProjectDirectories {
+        let name = trim_and_replace_spaces_with_hyphens_then_lowercase(project_name);
+        SystemProjectDirectories::from_unprocessed_string(&name, &SystemDirectories::with_prefix(prefix))
+    }
+
+    pub fn from_qualified_project_name(qualified_project_name: &str) -> SystemProjectDirectories {
+        SystemProjectDirectories::from_qualified_project_name_with_prefix(qualified_project_name, "/usr")
+    }
+
+    pub fn from_qualified_project_name_with_prefix<P: Into<PathBuf>>(qualified_project_name: &str, prefix: P) -> SystemProjectDirectories {
+        let name = strip_qualification(qualified_project_name).to_lowercase();
+        SystemProjectDirectories::from_unprocessed_string(name.trim(), &SystemDirectories::with_prefix(prefix))
+    }
+
+    pub fn project_name(&self) -> &str {
+        self.project_name.as_str()
+    }
+    pub fn project_cache_dir(&self) -> &Path {
+        self.project_cache_dir.as_path()
+    }
+    pub fn project_config_dir(&self) -> &Path {
+        self.project_config_dir.as_path()
+    }
+    pub fn project_log_dir(&self) -> &Path {
+        self.project_log_dir.as_path()
+    }
+    pub fn project_runtime_dir(&self) -> &Path {
+        self.project_runtime_dir.as_path()
+    }
+    pub fn project_state_dir(&self) -> &Path {
+        self.project_state_dir.as_path()
+    }
+}
+
+/// Maps the system layout onto the per-user accessors: the state directory
+/// serves as both data directories, the log directory has no counterpart.
+impl From<SystemProjectDirectories> for ProjectDirectories {
+    fn from(system: SystemProjectDirectories) -> ProjectDirectories {
+        ProjectDirectories {
+            project_name: system.project_name,
+            project_cache_dir: system.project_cache_dir,
+            project_config_dir: system.project_config_dir,
+            project_data_dir: system.project_state_dir.clone(),
+            project_data_local_dir: system.project_state_dir,
+            project_runtime_dir: Some(system.project_runtime_dir),
+            service_dirs: None,
+        }
+    }
+}
+
+impl ProjectDirectories {
+    /// Chooses between the per-user and the system-wide layout based on the
+    /// effective uid of the current process, see `SystemDirectories::is_system_user`.
+    pub fn from_project_name_for_effective_uid(project_name: &str) -> ProjectDirectories {
+        ProjectDirectories::from_project_name_for_effective_uid_with_prefix(project_name, "/usr")
+    }
+
+    /// Like `from_project_name_for_effective_uid`, but the system-wide layout is
+    /// derived from the given install prefix, see `SystemDirectories::with_prefix`.
+    pub fn from_project_name_for_effective_uid_with_prefix<P: Into<PathBuf>>(project_name: &str, prefix: P) -> ProjectDirectories {
+        if SystemDirectories::is_system_user() {
+            SystemProjectDirectories::from_project_name_with_prefix(project_name, prefix).into()
+        } else {
+            ProjectDirectories::from_project_name(project_name)
+        }
+    }
+}
+
+/// Returns whether `uid` lies outside of the range of regular users defined by
+/// the contents of a `login.defs(5)` file.
+fn is_system_uid(uid: u32, login_defs: &str) -> bool {
+    let uid_min = parse_login_defs_uid(login_defs, "UID_MIN").unwrap_or(DEFAULT_UID_MIN);
+    let uid_max = parse_login_defs_uid(login_defs, "UID_MAX").unwrap_or(DEFAULT_UID_MAX);
+    uid == 0 || uid < uid_min || uid > uid_max
+}
+
+/// Reads the uid defined as `key`, e. g. `UID_MIN`, from the contents of a
+/// `login.defs(5)` file, where later definitions override earlier ones.
+fn parse_login_defs_uid(content: &str, key: &str) -> Option<u32> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) if name == key => value.parse().ok(),
+                _ => None,
+            }
+        })
+        .next_back()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use system::is_system_uid;
+    use system::parse_login_defs_uid;
+    use system::SystemProjectDirectories;
+
+    #[test]
+    fn test_is_system_uid() {
+        let content = "# UID_MIN 100\nMAIL_DIR /var/mail\nUID_MIN\t\t 500\nUID_MAX 50000\n";
+        assert_eq!(parse_login_defs_uid(content, "UID_MIN"), Some(500));
+        assert_eq!(parse_login_defs_uid(content, "UID_MAX"), Some(50000));
+        assert_eq!(parse_login_defs_uid("UID_MIN many\n", "UID_MIN"), None);
+        assert_eq!(parse_login_defs_uid("", "UID_MIN"), None);
+
+        assert!(is_system_uid(0, content));
+        assert!(is_system_uid(499, content));
+        assert!(!is_system_uid(500, content));
+        assert!(is_system_uid(50001, content));
+        assert!(is_system_uid(65534, ""));
+        assert!(!is_system_uid(1000, ""));
+    }
+
+    #[test]
+    fn test_system_project_directories_prefix() {
+        let usr = SystemProjectDirectories::from_project_name("Foo Daemon");
+        assert_eq!(usr.project_config_dir(), Path::new("/etc/foo-daemon"));
+        assert_eq!(usr.project_state_dir(), Path::new("/var/lib/foo-daemon"));
+        assert_eq!(usr.project_cache_dir(), Path::new("/var/cache/foo-daemon"));
+        assert_eq!(usr.project_log_dir(), Path::new("/var/log/foo-daemon"));
+        assert_eq!(usr.project_runtime_dir(), Path::new("/run/foo-daemon"));
+
+        let local = SystemProjectDirectories::from_project_name_with_prefix("Foo Daemon", "/usr/local");
+        assert_eq!(local.project_config_dir(), Path::new("/usr/local/etc/foo-daemon"));
+        assert_eq!(local.project_state_dir(), Path::new("/usr/local/var/lib/foo-daemon"));
+        assert_eq!(local.project_runtime_dir(), Path::new("/usr/local/var/run/foo-daemon"));
+
+        let opt = SystemProjectDirectories::from_qualified_project_name_with_prefix("org.foo.Daemon", "/opt/foo");
+        assert_eq!(opt.project_config_dir(), Path::new("/opt/foo/etc/daemon"));
+    }
+}
@@ -0,0 +1,232 @@
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use ProjectDirectories;
+
+/// The directories systemd created for a service unit.
+///
+/// If a unit sets `StateDirectory=`, `CacheDirectory=`, `LogsDirectory=`,
+/// `RuntimeDirectory=` or `ConfigurationDirectory=`, systemd exports the
+/// resulting absolute paths as `$STATE_DIRECTORY`, `$CACHE_DIRECTORY`, etc.
+/// Each variable may contain a colon-separated list if the unit lists more
+/// than one directory; all entries are kept in the order systemd provides them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ServiceDirectories {
+    cache_dirs: Vec<PathBuf>,
+    config_dirs: Vec<PathBuf>,
+    logs_dirs: Vec<PathBuf>,
+    runtime_dirs: Vec<PathBuf>,
+    state_dirs: Vec<PathBuf>,
+}
+
+#[deny(missing_docs)]
+impl ServiceDirectories {
+    /// Reads the service directories from the environment of the current process.
+    ///
+    /// Returns `None` if none of the variables is set, i. e. if the process
+    /// has not been started by systemd with any of the directory settings.
+    pub fn from_env() -> Option<ServiceDirectories> {
+        ServiceDirectories::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Reads the service directories through the given variable lookup.
+    pub fn from_lookup<F: Fn(&str) -> Option<String>>(lookup: F) -> Option<ServiceDirectories> {
+        let dirs = ServiceDirectories {
+            cache_dirs: split_directory_list(lookup("CACHE_DIRECTORY")),
+            config_dirs: split_directory_list(lookup("CONFIGURATION_DIRECTORY")),
+            logs_dirs: split_directory_list(lookup("LOGS_DIRECTORY")),
+            runtime_dirs: split_directory_list(lookup("RUNTIME_DIRECTORY")),
+            state_dirs: split_directory_list(lookup("STATE_DIRECTORY")),
+        };
+        if dirs == ServiceDirectories::default() {
+            None
+        } else {
+            Some(dirs)
+        }
+    }
+
+    /// Returns the entries of `$CACHE_DIRECTORY`.
+    pub fn cache_dirs(&self) -> &[PathBuf] {
+        &self.cache_dirs
+    }
+
+    /// Returns the entries of `$CONFIGURATION_DIRECTORY`.
+    pub fn config_dirs(&self) -> &[PathBuf] {
+        &self.config_dirs
+    }
+
+    /// Returns the entries of `$LOGS_DIRECTORY`.
+    pub fn logs_dirs(&self) -> &[PathBuf] {
+        &self.logs_dirs
+    }
+
+    /// Returns the entries of `$RUNTIME_DIRECTORY`.
+    pub fn runtime_dirs(&self) -> &[PathBuf] {
+        &self.runtime_dirs
+    }
+
+    /// Returns the entries of `$STATE_DIRECTORY`.
+    pub fn state_dirs(&self) -> &[PathBuf] {
+        &self.state_dirs
+    }
+}
+
+/// The reasons a credential could not be located.
+#[derive(Debug)]
+pub enum CredentialError {
+    /// The name is empty, `.` or `..`, or contains a `/`.
+    InvalidName(String),
+    /// Neither `$CREDENTIALS_DIRECTORY` nor the fallback location contain the credential.
+    NotFound(PathBuf),
+    /// The fallback file can be read by every user and is therefore refused.
+    WorldReadable(PathBuf),
+    /// The metadata of the fallback file could not be read.
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CredentialError::InvalidName(ref name) => write!(f, "invalid credential name {:?}", name),
+            CredentialError::NotFound(ref path) => write!(f, "credential not found at {}", path.display()),
+            CredentialError::WorldReadable(ref path) => {
+                write!(f, "refusing world-readable credential file {}", path.display())
+            }
+            CredentialError::Io(ref path, ref err) => write!(f, "cannot access {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl Error for CredentialError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            CredentialError::Io(_, ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl ProjectDirectories {
+    /// Returns the path of the credential with the given name.
+    ///
+    /// Services started with `LoadCredential=` or `SetCredential=` find their
+    /// secrets in `$CREDENTIALS_DIRECTORY`. Outside of systemd, the credential is
+    /// looked up as `credentials/<name>` inside of `project_config_dir`; such a
+    /// fallback file is refused if it is readable by every user.
+    pub fn credential_path(&self, name: &str) -> Result<PathBuf, CredentialError> {
+        let credentials_dir = env::var("CREDENTIALS_DIRECTORY").ok().map(PathBuf::from);
+        find_credential(name, credentials_dir, &self.project_config_dir.join("credentials"))
+    }
+}
+
+fn find_credential(name: &str, credentials_dir: Option<PathBuf>, fallback_dir: &Path) -> Result<PathBuf, CredentialError> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return Err(CredentialError::InvalidName(String::from(name)));
+    }
+    if let Some(dir) = credentials_dir.filter(|dir| dir.is_absolute()) {
+        let path = dir.join(name);
+        return if path.is_file() {
+            Ok(path)
+        } else {
+            Err(CredentialError::NotFound(path))
+        };
+    }
+    let path = fallback_dir.join(name);
+    match fs::metadata(&path) {
+        Ok(ref metadata) if metadata.is_file() => {
+            if is_world_readable(metadata) {
+                Err(CredentialError::WorldReadable(path))
+            } else {
+                Ok(path)
+            }
+        }
+        Ok(_) => Err(CredentialError::NotFound(path)),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Err(CredentialError::NotFound(path)),
+        Err(err) => Err(CredentialError::Io(path, err)),
+    }
+}
+
+#[cfg(unix)]
+fn is_world_readable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o004 != 0
+}
+
+#[cfg(not(unix))]
+fn is_world_readable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Splits a colon-separated list of paths, dropping empty and relative entries.
+fn split_directory_list(value: Option<String>) -> Vec<PathBuf> {
+    value
+        .map(|list| {
+            list.split(':')
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use systemd::find_credential;
+    use systemd::CredentialError;
+    use systemd::ServiceDirectories;
+
+    #[test]
+    fn test_service_directories_from_lookup() {
+        let actual1 = ServiceDirectories::from_lookup(|_| None);
+        assert_eq!(actual1, None);
+
+        let actual2 = ServiceDirectories::from_lookup(|key| match key {
+            "STATE_DIRECTORY" => Some(String::from("/var/lib/foo:/var/lib/bar::relative")),
+            "CACHE_DIRECTORY" => Some(String::from("/var/cache/foo")),
+            _ => None,
+        }).unwrap();
+        let expected2 = vec![PathBuf::from("/var/lib/foo"), PathBuf::from("/var/lib/bar")];
+        assert_eq!(actual2.state_dirs(), expected2.as_slice());
+        assert_eq!(actual2.cache_dirs(), &[PathBuf::from("/var/cache/foo")]);
+        assert!(actual2.runtime_dirs().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_credential() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("directories-credentials-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let secret = dir.join("secret");
+        fs::write(&secret, "hunter2").unwrap();
+
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(find_credential("secret", None, &dir).unwrap(), secret);
+        assert_eq!(find_credential("secret", Some(dir.clone()), &PathBuf::from("/nonexistent")).unwrap(), secret);
+
+        fs::set_permissions(&secret, fs::Permissions::from_mode(0o644)).unwrap();
+        match find_credential("secret", None, &dir) {
+            Err(CredentialError::WorldReadable(path)) => assert_eq!(path, secret),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match find_credential("../secret", None, &dir) {
+            Err(CredentialError::InvalidName(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        match find_credential("missing", None, &dir) {
+            Err(CredentialError::NotFound(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
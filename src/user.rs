@@ -0,0 +1,292 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use lin::parse_user_dirs;
+use strip_qualification;
+use trim_and_replace_spaces_with_hyphens_then_lowercase;
+use passwd::PasswdDatabase;
+use passwd::PasswdEntry;
+use ProjectDirectories;
+
+/// The base directories of an arbitrary user, e. g. for administrative tools
+/// running as root on behalf of another user.
+///
+/// The environment of the current process belongs to a different user, so
+/// none of its variables are consulted. Instead, the directories are derived
+/// from the user's entry in the password database:
+///
+/// - the home directory is the one listed in the password database, which
+///   includes users of directory services like LDAP, sssd or systemd-homed,
+/// - cache, config and data directories are the XDG defaults below it
+///   (`~/.cache`, `~/.config`, `~/.local/share`),
+/// - media directories are read from the user's `~/.config/user-dirs.dirs`,
+/// - the runtime directory is always `/run/user/<uid>`, the location
+///   `pam_systemd` uses for `$XDG_RUNTIME_DIR`, whether or not the user
+///   is currently logged in.
+#[derive(Debug, Clone)]
+pub struct UserDirectories {
+    user: PasswdEntry,
+
+    home_dir: PathBuf,
+    cache_dir: PathBuf,
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+    executable_dir: PathBuf,
+    runtime_dir: PathBuf,
+
+    audio_dir: PathBuf,
+    desktop_dir: PathBuf,
+    document_dir: PathBuf,
+    download_dir: PathBuf,
+    font_dir: PathBuf,
+    picture_dir: PathBuf,
+    public_dir: PathBuf,
+    template_dir: PathBuf,
+    video_dir: PathBuf,
+}
+
+/// The reasons the directories of another user could not be determined.
+#[derive(Debug)]
+pub enum UserLookupError {
+    /// The password database has no entry with this name.
+    UnknownName(String),
+    /// The password database has no entry with this uid.
+    UnknownUid(u32),
+    /// The home directory listed in the password database is not absolute.
+    NotAbsolute(PathBuf),
+    /// The password database could not be queried.
+    Database(io::Error),
+    /// `user-dirs.dirs` could not be read.
+    Io(PathBuf, io::Error),
+}
+
+#[deny(missing_docs)]
+impl UserDirectories {
+    /// Returns the directories of the user with the given name.
+    pub fn from_user_name(name: &str) -> Result<UserDirectories, UserLookupError> {
+        match PasswdDatabase::System.find_name(name) {
+            Ok(Some((entry, _))) => UserDirectories::from_passwd_entry(entry),
+            Ok(None) => Err(UserLookupError::UnknownName(String::from(name))),
+            Err(err) => Err(UserLookupError::Database(err)),
+        }
+    }
+
+    /// Returns the directories of the user with the given uid.
+    pub fn from_uid(uid: u32) -> Result<UserDirectories, UserLookupError> {
+        match PasswdDatabase::System.find_uid(uid) {
+            Ok(Some((entry, _))) => UserDirectories::from_passwd_entry(entry),
+            Ok(None) => Err(UserLookupError::UnknownUid(uid)),
+            Err(err) => Err(UserLookupError::Database(err)),
+        }
+    }
+
+    /// Returns the directories of the user described by the given password database entry.
+    pub fn from_passwd_entry(user: PasswdEntry) -> Result<UserDirectories, UserLookupError> {
+        let home_dir = user.home_dir().to_path_buf();
+        if !home_dir.is_absolute() {
+            return Err(UserLookupError::NotAbsolute(home_dir));
+        }
+        let config_dir = home_dir.join(".config");
+        let data_dir = home_dir.join(".local/share");
+
+        let user_dirs_path = config_dir.join("user-dirs.dirs");
+        let user_dirs = match fs::read_to_string(&user_dirs_path) {
+            Ok(content) => parse_user_dirs(&content, &home_dir),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Default::default(),
+            Err(err) => return Err(UserLookupError::Io(user_dirs_path, err)),
+        };
+        // Same fallbacks as xdg-user-dir: the desktop defaults to ~/Desktop, everything else to ~.
+        let user_dir = |name: &str| user_dirs.get(name).cloned().unwrap_or_else(|| home_dir.clone());
+
+        Ok(UserDirectories {
+            cache_dir: home_dir.join(".cache"),
+            executable_dir: home_dir.join(".local/bin"),
+            runtime_dir: PathBuf::from(format!("/run/user/{}", user.uid())),
+            font_dir: data_dir.join("fonts"),
+
+            audio_dir: user_dir("MUSIC"),
+            desktop_dir: user_dirs.get("DESKTOP").cloned().unwrap_or_else(|| home_dir.join("Desktop")),
+            document_dir: user_dir("DOCUMENTS"),
+            download_dir: user_dir("DOWNLOAD"),
+            picture_dir: user_dir("PICTURES"),
+            public_dir: user_dir("PUBLICSHARE"),
+            template_dir: user_dir("TEMPLATES"),
+            video_dir: user_dir("VIDEOS"),
+
+            user,
+            home_dir,
+            config_dir,
+            data_dir,
+        })
+    }
+
+    /// Returns the password database entry of the user.
+    pub fn user(&self) -> &PasswdEntry {
+        &self.user
+    }
+
+    /// Returns the user's home directory, as listed in the password database.
+    pub fn home_dir(&self) -> &Path {
+        self.home_dir.as_path()
+    }
+
+    /// Returns the user's cache directory, `~/.cache/`.
+    pub fn cache_dir(&self) -> &Path {
+        self.cache_dir.as_path()
+    }
+
+    /// Returns the user's config directory, `~/.config/`.
+    pub fn config_dir(&self) -> &Path {
+        self.config_dir.as_path()
+    }
+
+    /// Returns the user's data directory, `~/.local/share/`.
+    pub fn data_dir(&self) -> &Path {
+        self.data_dir.as_path()
+    }
+
+    /// Returns the user's executable directory, `~/.local/bin/`.
+    pub fn executable_dir(&self) -> &Path {
+        self.executable_dir.as_path()
+    }
+
+    /// Returns the user's runtime directory, `/run/user/<uid>/`.
+    ///
+    /// This is derived from the uid, not read from `$XDG_RUNTIME_DIR`.
+    pub fn runtime_dir(&self) -> &Path {
+        self.runtime_dir.as_path()
+    }
+
+    /// Returns the user's audio directory, `XDG_MUSIC_DIR` in `user-dirs.dirs`.
+    pub fn audio_dir(&self) -> &Path {
+        self.audio_dir.as_path()
+    }
+
+    /// Returns the user's desktop directory, `XDG_DESKTOP_DIR` in `user-dirs.dirs`.
+    pub fn desktop_dir(&self) -> &Path {
+        self.desktop_dir.as_path()
+    }
+
+    /// Returns the user's document directory, `XDG_DOCUMENTS_DIR` in `user-dirs.dirs`.
+    pub fn document_dir(&self) -> &Path {
+        self.document_dir.as_path()
+    }
+
+    /// Returns the user's download directory, `XDG_DOWNLOAD_DIR` in `user-dirs.dirs`.
+    pub fn download_dir(&self) -> &Path {
+        self.download_dir.as_path()
+    }
+
+    /// Returns the user's font directory, `~/.local/share/fonts/`.
+    pub fn font_dir(&self) -> &Path {
+        self.font_dir.as_path()
+    }
+
+    /// Returns the user's picture directory, `XDG_PICTURES_DIR` in `user-dirs.dirs`.
+    pub fn picture_dir(&self) -> &Path {
+        self.picture_dir.as_path()
+    }
+
+    /// Returns the user's public directory, `XDG_PUBLICSHARE_DIR` in `user-dirs.dirs`.
+    pub fn public_dir(&self) -> &Path {
+        self.public_dir.as_path()
+    }
+
+    /// Returns the user's template directory, `XDG_TEMPLATES_DIR` in `user-dirs.dirs`.
+    pub fn template_dir(&self) -> &Path {
+        self.template_dir.as_path()
+    }
+
+    /// Returns the user's video directory, `XDG_VIDEOS_DIR` in `user-dirs.dirs`.
+    pub fn video_dir(&self) -> &Path {
+        self.video_dir.as_path()
+    }
+}
+
+impl fmt::Display for UserLookupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UserLookupError::UnknownName(ref name) => write!(f, "no passwd entry for user {:?}", name),
+            UserLookupError::UnknownUid(uid) => write!(f, "no passwd entry for uid {}", uid),
+            UserLookupError::NotAbsolute(ref path) => write!(f, "home directory {:?} is not absolute", path),
+            UserLookupError::Database(ref err) => write!(f, "cannot query the password database: {}", err),
+            UserLookupError::Io(ref path, ref err) => write!(f, "cannot read {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl Error for UserLookupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            UserLookupError::Database(ref err) => Some(err),
+            UserLookupError::Io(_, ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl ProjectDirectories {
+    /// Computes the project directories of another user, see `UserDirectories`.
+    pub fn from_unprocessed_string_for_user(value: &str, user_dirs: &UserDirectories) -> ProjectDirectories {
+        let project_data_dir = user_dirs.data_dir().join(value);
+        ProjectDirectories {
+            project_name: String::from(value),
+            project_cache_dir: user_dirs.cache_dir().join(value),
+            project_config_dir: user_dirs.config_dir().join(value),
+            project_data_local_dir: project_data_dir.clone(),
+            project_data_dir,
+            project_runtime_dir: Some(user_dirs.runtime_dir().join(value)),
+            service_dirs: None,
+        }
+    }
+
+    pub fn from_project_name_for_user(project_name: &str, user_dirs: &UserDirectories) -> ProjectDirectories {
+        let name = trim_and_replace_spaces_with_hyphens_then_lowercase(project_name);
+        ProjectDirectories::from_unprocessed_string_for_user(&name, user_dirs)
+    }
+
+    pub fn from_qualified_project_name_for_user(qualified_project_name: &str, user_dirs: &UserDirectories) -> ProjectDirectories {
+        let name = strip_qualification(qualified_project_name).to_lowercase();
+        ProjectDirectories::from_unprocessed_string_for_user(name.trim(), user_dirs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use passwd::PasswdEntry;
+    use user::UserDirectories;
+    use ProjectDirectories;
+
+    #[test]
+    fn test_user_directories_from_passwd_entry() {
+        let home = env::temp_dir().join(format!("directories-user-{}", ::std::process::id()));
+        fs::create_dir_all(home.join(".config")).unwrap();
+        fs::write(home.join(".config/user-dirs.dirs"), "XDG_MUSIC_DIR=\"$HOME/Musik\"\n").unwrap();
+
+        let line = format!("alice:x:1234:1234::{}:/bin/sh", home.display());
+        let user_dirs = UserDirectories::from_passwd_entry(PasswdEntry::parse(&line).unwrap()).unwrap();
+        assert_eq!(user_dirs.config_dir(), home.join(".config"));
+        assert_eq!(user_dirs.runtime_dir(), Path::new("/run/user/1234"));
+        assert_eq!(user_dirs.audio_dir(), home.join("Musik"));
+        assert_eq!(user_dirs.desktop_dir(), home.join("Desktop"));
+        assert_eq!(user_dirs.video_dir(), home.as_path());
+
+        let project_dirs = ProjectDirectories::from_project_name_for_user("Bar App", &user_dirs);
+        assert_eq!(project_dirs.project_config_dir(), home.join(".config/bar-app"));
+        assert_eq!(project_dirs.project_runtime_dir(), Some(Path::new("/run/user/1234/bar-app")));
+        let qualified_dirs = ProjectDirectories::from_qualified_project_name_for_user("org.foo.BarApp", &user_dirs);
+        assert_eq!(qualified_dirs.project_data_dir(), home.join(".local/share/barapp"));
+
+        let root_dirs = UserDirectories::from_uid(0).unwrap();
+        assert_eq!(root_dirs.user().name(), "root");
+
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Mutex;
+use std::sync::RwLock;
+
+use provenance::Rejected;
+use BaseDirectories;
+
+static WARNING_HANDLER: RwLock<Option<fn(&Rejected)>> = RwLock::new(None);
+static REPORTED: Mutex<Vec<Rejected>> = Mutex::new(Vec::new());
+
+/// Installs a function that is called for every value ignored while resolving
+/// directories, e. g. a relative `$XDG_CACHE_HOME`, or removes it with `None`.
+///
+/// Each distinct rejected value is reported only once per process. If the `log`
+/// feature is enabled, rejected values are additionally logged at warning level.
+///
+/// Only values from the environment of the current process are reported, not
+/// those of values created with `BaseDirectories::from_env`.
+pub fn set_warning_handler(handler: Option<fn(&Rejected)>) {
+    *WARNING_HANDLER.write().unwrap_or_else(|err| err.into_inner()) = handler;
+}
+
+/// Hands values rejected while resolving the directories of `dirs` over to the
+/// warning handler and the `log` crate, unless `dirs` has been created with
+/// `BaseDirectories::from_env`.
+///
+/// Values are only marked as reported once they have been delivered, and the
+/// handler is called without holding any lock, so it may resolve directories itself.
+pub fn report(dirs: &BaseDirectories, rejected: &[Rejected]) {
+    if dirs.env.is_some() {
+        return;
+    }
+    let handler = *WARNING_HANDLER.read().unwrap_or_else(|err| err.into_inner());
+    if rejected.is_empty() || (handler.is_none() && !cfg!(feature = "log")) {
+        return;
+    }
+    let mut new_rejected = Vec::new();
+    {
+        let mut reported = REPORTED.lock().unwrap_or_else(|err| err.into_inner());
+        for rejected in rejected {
+            if !reported.contains(rejected) {
+                reported.push(rejected.clone());
+                new_rejected.push(rejected);
+            }
+        }
+    }
+    for rejected in new_rejected {
+        #[cfg(feature = "log")]
+        warn!("{}", rejected);
+        if let Some(handler) = handler {
+            handler(rejected);
+        }
+    }
+}
+
+impl BaseDirectories {
+    /// Returns every value ignored while resolving the directories, without duplicates.
+    ///
+    /// Unlike the warning handler, this reflects the current environment on
+    /// every call, regardless of what has been reported before.
+    pub fn warnings(&self) -> Vec<Rejected> {
+        let mut warnings: Vec<Rejected> = Vec::new();
+        for explanation in self.explain() {
+            for rejected in explanation.rejected() {
+                if !warnings.contains(rejected) {
+                    warnings.push(rejected.clone());
+                }
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::slice;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use provenance::Rejected;
+    use provenance::RejectionReason;
+    use provenance::Source;
+    use warnings::report;
+    use warnings::set_warning_handler;
+    use BaseDirectories;
+
+    static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+    fn count(rejected: &Rejected) {
+        if rejected.source() == &Source::EnvVar(String::from("XDG_TEST_HOME")) {
+            HANDLED.fetch_add(1, Ordering::SeqCst);
+        }
+        // resolving directories from the handler must not deadlock
+        let nested = Rejected::new(Source::EnvVar(String::from("XDG_NESTED_HOME")), "nested", RejectionReason::NotAbsolute);
+        report(&BaseDirectories::new(), &[nested]);
+    }
+
+    #[test]
+    fn test_report() {
+        let source = Source::EnvVar(String::from("XDG_TEST_HOME"));
+        let relative = Rejected::new(source.clone(), "relative", RejectionReason::NotAbsolute);
+        let empty = Rejected::new(source, "", RejectionReason::Empty);
+        let dirs = BaseDirectories::new();
+        let simulated = BaseDirectories::from_env(vec![("XDG_TEST_HOME", "relative")]);
+
+        if !cfg!(feature = "log") {
+            set_warning_handler(None);
+            report(&dirs, slice::from_ref(&empty));
+        }
+        set_warning_handler(Some(count));
+        report(&simulated, slice::from_ref(&relative));
+        report(&dirs, &[relative.clone(), empty]);
+        report(&dirs, &[relative]);
+        set_warning_handler(None);
+
+        assert_eq!(HANDLED.load(Ordering::SeqCst), 2);
+    }
+}
@@ -2,92 +2,174 @@ use std;
 use std::path::PathBuf;
 
 extern crate winapi;
+use self::winapi::shared::winerror;
 use self::winapi::um::knownfolders;
 use self::winapi::um::combaseapi;
 use self::winapi::um::shlobj;
 use self::winapi::um::shtypes;
 use self::winapi::um::winnt;
 
+use kind::DirectoryKind;
+use layout::windows_folders;
+use layout::KnownFolder;
+use layout::Platform;
+use layout::PlatformFolders;
+use BaseDirBackend;
 use BaseDirectories;
 use ProjectDirectories;
-use strip_qualification;
-
-pub fn base_directories() -> BaseDirectories {
-    let home_dir       = unsafe { known_folder(&knownfolders::FOLDERID_Profile) };
-    let data_dir       = unsafe { known_folder(&knownfolders::FOLDERID_RoamingAppData) };
-    let data_local_dir = unsafe { known_folder(&knownfolders::FOLDERID_LocalAppData) };
-    let desktop_dir    = unsafe { known_folder(&knownfolders::FOLDERID_Desktop) };
-    let document_dir   = unsafe { known_folder(&knownfolders::FOLDERID_Documents) };
-    let download_dir   = unsafe { known_folder(&knownfolders::FOLDERID_Downloads) };
-    let audio_dir      = unsafe { known_folder(&knownfolders::FOLDERID_Music) };
-    let picture_dir    = unsafe { known_folder(&knownfolders::FOLDERID_Pictures) };
-    let public_dir     = unsafe { known_folder(&knownfolders::FOLDERID_Public) };
-    let template_dir   = unsafe { known_folder(&knownfolders::FOLDERID_Templates) };
-    let video_dir      = unsafe { known_folder(&knownfolders::FOLDERID_Videos) };
-
-    let cache_dir      = data_local_dir.join("cache");
-    let config_dir     = data_dir.clone();
-
-    BaseDirectories {
-        home_dir:       home_dir,
-        cache_dir:      cache_dir,
-        config_dir:     config_dir,
-        data_dir:       data_dir,
-        data_local_dir: data_local_dir,
-        executable_dir: None,
-        runtime_dir:    None,
-
-        audio_dir:      audio_dir,
-        desktop_dir:    desktop_dir,
-        document_dir:   document_dir,
-        download_dir:   download_dir,
-        font_dir:       None,
-        picture_dir:    picture_dir,
-        public_dir:     public_dir,
-        template_dir:   Some(template_dir),
-        video_dir:      video_dir
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OsBackend;
+
+impl BaseDirBackend for OsBackend {
+    fn home_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Home).unwrap()
+    }
+
+    fn cache_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Cache).unwrap()
+    }
+
+    fn config_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Config).unwrap()
+    }
+
+    fn data_roaming_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::DataRoaming).unwrap()
+    }
+
+    fn data_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Data).unwrap()
+    }
+
+    fn data_low_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::DataLow)
+    }
+
+    fn program_data_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::ProgramData)
+    }
+
+    fn executable_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::Executable)
+    }
+
+    fn user_program_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::UserProgram)
+    }
+
+    fn system_font_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::SystemFont)
+    }
+
+    fn runtime_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::Runtime)
+    }
+
+    fn audio_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Audio).unwrap()
+    }
+
+    fn desktop_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Desktop).unwrap()
+    }
+
+    fn document_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Document).unwrap()
+    }
+
+    fn download_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Download).unwrap()
+    }
+
+    fn font_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::Font)
+    }
+
+    fn picture_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Picture).unwrap()
+    }
+
+    fn public_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Public).unwrap()
+    }
+
+    fn template_dir(&self, _: &BaseDirectories) -> Option<PathBuf> {
+        base_dir(DirectoryKind::Template)
+    }
+
+    fn video_dir(&self, _: &BaseDirectories) -> PathBuf {
+        base_dir(DirectoryKind::Video).unwrap()
     }
 }
 
-impl ProjectDirectories {
-    pub fn from_unprocessed_string(value: &str) -> ProjectDirectories {
-        let project_name             = String::from(value);
-        let data_local_dir           = unsafe { known_folder(&knownfolders::FOLDERID_LocalAppData) };
+/// The kinds of the directories `ProjectDirectories` are located in.
+const PROJECT_KINDS: [DirectoryKind; 4] =
+    [DirectoryKind::Cache, DirectoryKind::Config, DirectoryKind::DataRoaming, DirectoryKind::Data];
 
-        let project_cache_dir        = data_local_dir.join(&value).join("cache");
-        let project_data_local_dir   = data_local_dir.join(&value);
-        let project_data_dir = unsafe { known_folder(&knownfolders::FOLDERID_RoamingAppData) }.join(&value);
+/// Returns the inputs of the Windows layout for the given kinds, as reported by the Known Folder API.
+fn folders(kinds: &[DirectoryKind]) -> PlatformFolders {
+    windows_folders(kinds, |folder| unsafe { known_folder(folder_id(folder)) })
+}
 
-        let project_config_dir       = project_data_dir.clone();
+/// Returns the directory of the given kind, querying only the known folders it depends on.
+fn base_dir(kind: DirectoryKind) -> Option<PathBuf> {
+    Platform::Windows.base_dir(kind, &folders(&[kind]))
+}
 
-        ProjectDirectories {
-            project_name:           project_name,
-            project_cache_dir:      project_cache_dir,
-            project_config_dir:     project_config_dir,
-            project_data_dir:       project_data_dir,
-            project_data_local_dir: project_data_local_dir,
-            project_runtime_dir:    None
-        }
+fn folder_id(folder: KnownFolder) -> shtypes::REFKNOWNFOLDERID {
+    match folder {
+        KnownFolder::Profile => &knownfolders::FOLDERID_Profile,
+        KnownFolder::RoamingAppData => &knownfolders::FOLDERID_RoamingAppData,
+        KnownFolder::LocalAppData => &knownfolders::FOLDERID_LocalAppData,
+        KnownFolder::LocalAppDataLow => &knownfolders::FOLDERID_LocalAppDataLow,
+        KnownFolder::Desktop => &knownfolders::FOLDERID_Desktop,
+        KnownFolder::Documents => &knownfolders::FOLDERID_Documents,
+        KnownFolder::Downloads => &knownfolders::FOLDERID_Downloads,
+        KnownFolder::Music => &knownfolders::FOLDERID_Music,
+        KnownFolder::Pictures => &knownfolders::FOLDERID_Pictures,
+        KnownFolder::Public => &knownfolders::FOLDERID_Public,
+        KnownFolder::ProgramData => &knownfolders::FOLDERID_ProgramData,
+        KnownFolder::Fonts => &knownfolders::FOLDERID_Fonts,
+        KnownFolder::UserProgramFiles => &knownfolders::FOLDERID_UserProgramFiles,
+        KnownFolder::Templates => &knownfolders::FOLDERID_Templates,
+        KnownFolder::Videos => &knownfolders::FOLDERID_Videos,
+    }
+}
+
+impl ProjectDirectories {
+    pub fn from_unprocessed_string(value: &str) -> ProjectDirectories {
+        Platform::Windows.project_dirs_from_unprocessed_string(value, &folders(&PROJECT_KINDS))
     }
 
     pub fn from_project_name(project_name: &str) -> ProjectDirectories {
-        ProjectDirectories::from_unprocessed_string(project_name)
+        Platform::Windows.project_dirs_from_project_name(project_name, &folders(&PROJECT_KINDS))
     }
 
     pub fn from_qualified_project_name(qualified_project_name: &str) -> ProjectDirectories {
-        let name = strip_qualification(qualified_project_name).trim();
-        ProjectDirectories::from_unprocessed_string(name)
+        Platform::Windows.project_dirs_from_qualified_project_name(qualified_project_name, &folders(&PROJECT_KINDS))
+    }
+
+    pub fn from_qualified_project_name_nested(qualified_project_name: &str) -> ProjectDirectories {
+        Platform::Windows.project_dirs_from_qualified_project_name_nested(qualified_project_name, &folders(&PROJECT_KINDS))
     }
 }
 
-unsafe fn known_folder(folder_id: shtypes::REFKNOWNFOLDERID) -> PathBuf {
+/// Returns the path of the known folder, or `None` if it cannot be resolved,
+/// e. g. because it does not exist on this version of Windows.
+unsafe fn known_folder(folder_id: shtypes::REFKNOWNFOLDERID) -> Option<PathBuf> {
     let mut path_ptr: winnt::PWSTR = std::ptr::null_mut();
-    let _result = shlobj::SHGetKnownFolderPath(folder_id, 0, std::ptr::null_mut(), &mut path_ptr);
+    let result = shlobj::SHGetKnownFolderPath(folder_id, 0, std::ptr::null_mut(), &mut path_ptr);
+    if result != winerror::S_OK {
+        // the buffer has to be freed even if the call fails
+        combaseapi::CoTaskMemFree(path_ptr as *mut winapi::ctypes::c_void);
+        return None;
+    }
     let len = length_of_u16_string(path_ptr);
     let path = std::slice::from_raw_parts(path_ptr, len);
     let ostr: std::ffi::OsString = std::os::windows::ffi::OsStringExt::from_wide(path);
     combaseapi::CoTaskMemFree(path_ptr as *mut winapi::ctypes::c_void);
-    PathBuf::from(ostr)
+    Some(PathBuf::from(ostr))
 }
 
 unsafe fn length_of_u16_string(ptr: *mut u16) -> usize {
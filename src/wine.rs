@@ -0,0 +1,146 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use kind::DirectoryKind;
+use layout::Platform;
+use layout::PlatformFolders;
+use wsl::map_drive_path;
+use BaseDirectories;
+use ProjectDirectories;
+
+/// The directories of Windows programs running inside a Wine prefix, as seen
+/// from the host.
+///
+/// The directories are computed like on Windows, see `Platform::Windows`, for
+/// the profile `C:\users\<user>` Wine creates, and translated to the paths of
+/// the prefix, e. g. `C:\users\eve\AppData\Roaming` becomes
+/// `~/.wine/drive_c/users/eve/AppData/Roaming`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WinePrefix {
+    prefix: PathBuf,
+    user_name: String,
+    folders: PlatformFolders,
+}
+
+#[deny(missing_docs)]
+impl WinePrefix {
+    /// Returns the prefix of the current user, i. e. `$WINEPREFIX` or `~/.wine`.
+    ///
+    /// Returns `None` if the user name, which Wine takes from `$USER`, is unknown,
+    /// or if `$WINEPREFIX` is set to a relative path, which Wine itself refuses.
+    pub fn current() -> Option<WinePrefix> {
+        WinePrefix::with_base_dirs(&BaseDirectories::new())
+    }
+
+    /// Like `current`, but reading `$WINEPREFIX`, `$USER` and the home directory from `dirs`.
+    pub fn with_base_dirs(dirs: &BaseDirectories) -> Option<WinePrefix> {
+        let user_name = dirs.env_var("USER").filter(|name| !name.is_empty())?;
+        let prefix = match dirs.env_var("WINEPREFIX").filter(|prefix| !prefix.is_empty()) {
+            Some(prefix) => Some(PathBuf::from(prefix)).filter(|prefix| prefix.is_absolute())?,
+            None => dirs.home().join(".wine"),
+        };
+        Some(WinePrefix::new(prefix, &user_name))
+    }
+
+    /// Creates the view of the prefix at `prefix` for the Wine user `user_name`.
+    pub fn new<P: Into<PathBuf>>(prefix: P, user_name: &str) -> WinePrefix {
+        let folders = PlatformFolders::new(format!("C:\\users\\{}", user_name));
+        WinePrefix { prefix: prefix.into(), user_name: String::from(user_name), folders }
+    }
+
+    /// Returns the directory of the prefix.
+    pub fn prefix(&self) -> &Path {
+        self.prefix.as_path()
+    }
+
+    /// Returns the name of the Wine user.
+    pub fn user_name(&self) -> &str {
+        self.user_name.as_str()
+    }
+
+    /// Translates a Windows path of the prefix to a host path.
+    ///
+    /// `C:` is `drive_c` inside the prefix; all other drives are reached
+    /// through their links in `dosdevices`, e. g. `dosdevices/d:`.
+    /// Returns `None` for paths without a drive letter.
+    pub fn to_host_path(&self, windows_path: &Path) -> Option<PathBuf> {
+        map_drive_path(windows_path, |letter| match letter {
+            'c' => self.prefix.join("drive_c"),
+            _ => self.prefix.join("dosdevices").join(format!("{}:", letter)),
+        })
+    }
+
+    /// Returns the host path of the Windows directory of the given kind, see `BaseDirectories::get`.
+    pub fn get(&self, kind: DirectoryKind) -> Option<PathBuf> {
+        Platform::Windows
+            .base_dir(kind, &self.folders)
+            .and_then(|dir| self.to_host_path(&dir))
+    }
+
+    /// Computes the host paths of the directories `ProjectDirectories::from_unprocessed_string`
+    /// produces for a Windows program inside the prefix.
+    pub fn project_dirs_from_unprocessed_string(&self, value: &str) -> ProjectDirectories {
+        self.to_host_project_dirs(Platform::Windows.project_dirs_from_unprocessed_string(value, &self.folders))
+    }
+
+    /// Computes the host paths of the directories `ProjectDirectories::from_project_name`
+    /// produces for a Windows program inside the prefix.
+    pub fn project_dirs_from_project_name(&self, project_name: &str) -> ProjectDirectories {
+        self.to_host_project_dirs(Platform::Windows.project_dirs_from_project_name(project_name, &self.folders))
+    }
+
+    /// Computes the host paths of the directories `ProjectDirectories::from_qualified_project_name`
+    /// produces for a Windows program inside the prefix.
+    pub fn project_dirs_from_qualified_project_name(&self, qualified_project_name: &str) -> ProjectDirectories {
+        let project_dirs = Platform::Windows.project_dirs_from_qualified_project_name(qualified_project_name, &self.folders);
+        self.to_host_project_dirs(project_dirs)
+    }
+
+    /// Computes the host paths of the directories `ProjectDirectories::from_qualified_project_name_nested`
+    /// produces for a Windows program inside the prefix.
+    pub fn project_dirs_from_qualified_project_name_nested(&self, qualified_project_name: &str) -> ProjectDirectories {
+        let project_dirs = Platform::Windows.project_dirs_from_qualified_project_name_nested(qualified_project_name, &self.folders);
+        self.to_host_project_dirs(project_dirs)
+    }
+
+    /// Translates project directories computed for `C:\users\<user>`, which all have a drive letter.
+    fn to_host_project_dirs(&self, project_dirs: ProjectDirectories) -> ProjectDirectories {
+        let host_path = |path: &Path| self.to_host_path(path).unwrap();
+        ProjectDirectories {
+            project_cache_dir: host_path(&project_dirs.project_cache_dir),
+            project_config_dir: host_path(&project_dirs.project_config_dir),
+            project_data_dir: host_path(&project_dirs.project_data_dir),
+            project_data_local_dir: host_path(&project_dirs.project_data_local_dir),
+            project_runtime_dir: project_dirs.project_runtime_dir.as_ref().map(|dir| host_path(dir)),
+            ..project_dirs
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::path::PathBuf;
+    use kind::DirectoryKind;
+    use wine::WinePrefix;
+    use BaseDirectories;
+
+    #[test]
+    fn test_wine_prefix() {
+        let prefix = WinePrefix::new("/home/eve/.wine", "eve");
+        let project_dirs = prefix.project_dirs_from_qualified_project_name("com.Foo.Bar Game");
+        assert_eq!(project_dirs.project_name(), "Bar Game");
+        assert_eq!(project_dirs.project_config_dir(), Path::new("/home/eve/.wine/drive_c/users/eve/AppData/Roaming/Bar Game"));
+        assert_eq!(project_dirs.project_cache_dir(), Path::new("/home/eve/.wine/drive_c/users/eve/AppData/Local/Bar Game/cache"));
+        assert_eq!(prefix.get(DirectoryKind::Document), Some(PathBuf::from("/home/eve/.wine/drive_c/users/eve/Documents")));
+        assert_eq!(prefix.to_host_path(Path::new(r"D:\Games")), Some(PathBuf::from("/home/eve/.wine/dosdevices/d:/Games")));
+
+        let dirs = BaseDirectories::from_env(vec![("HOME", "/home/eve"), ("USER", "eve"), ("WINEPREFIX", "/srv/prefixes/game")]);
+        let custom = WinePrefix::with_base_dirs(&dirs).unwrap();
+        assert_eq!(custom.prefix(), Path::new("/srv/prefixes/game"));
+        assert_eq!(custom.user_name(), "eve");
+        let relative = BaseDirectories::from_env(vec![("HOME", "/home/eve"), ("USER", "eve"), ("WINEPREFIX", "game")]);
+        assert_eq!(WinePrefix::with_base_dirs(&relative), None);
+        assert_eq!(WinePrefix::with_base_dirs(&BaseDirectories::from_env(vec![("HOME", "/home/eve")])), None);
+    }
+}
@@ -0,0 +1,242 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use kind::DirectoryKind;
+use layout::KnownFolder;
+use layout::Platform;
+use layout::PlatformFolders;
+
+/// The directory below which WSL mounts the Windows drives by default.
+const DEFAULT_MOUNT_ROOT: &str = "/mnt";
+
+/// The entries of `C:\Users` which are not the profile of a person.
+const NON_PROFILE_DIRS: [&str; 6] = ["All Users", "Default", "Default User", "defaultuser0", "Public", "WDAGUtilityAccount"];
+
+/// The folders of the Windows user, as seen from a Linux distribution running
+/// inside the Windows Subsystem for Linux.
+///
+/// The directories are computed like on Windows, see `Platform::Windows`, and
+/// translated to the paths of the mounted drives, e. g. `C:\Users\Eve\Downloads`
+/// becomes `/mnt/c/Users/Eve/Downloads`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowsHostDirectories {
+    mount_root: PathBuf,
+    folders: PlatformFolders,
+}
+
+/// Returns whether the current process runs inside the Windows Subsystem for Linux.
+pub fn is_wsl() -> bool {
+    let distro_name = env::var("WSL_DISTRO_NAME").ok();
+    let proc_version = fs::read_to_string("/proc/version").ok();
+    is_wsl_with(distro_name.as_deref(), proc_version.as_deref())
+}
+
+/// Like `is_wsl`, but for the given value of `$WSL_DISTRO_NAME` and content of `/proc/version`.
+///
+/// `$WSL_DISTRO_NAME` is set by WSL 2 and recent versions of WSL 1; older
+/// versions are recognized by the kernel release, e. g. `4.4.0-19041-Microsoft`.
+pub fn is_wsl_with(distro_name: Option<&str>, proc_version: Option<&str>) -> bool {
+    if let Some(name) = distro_name {
+        if !name.is_empty() {
+            return true;
+        }
+    }
+    match proc_version {
+        Some(version) => {
+            let version = version.to_lowercase();
+            version.contains("microsoft") || version.contains("wsl")
+        }
+        None => false,
+    }
+}
+
+#[deny(missing_docs)]
+impl WindowsHostDirectories {
+    /// Finds the folders of the Windows user, if the current process runs inside WSL.
+    ///
+    /// The profile is taken from `$USERPROFILE`, if it is shared with WSL via
+    /// `$WSLENV`, either as a Windows path or, with the `/p` flag, as a Linux path
+    /// like `/mnt/c/Users/Eve`; otherwise it is the only profile in `/mnt/c/Users`.
+    /// Returns `None` outside of WSL or if the profile is ambiguous.
+    pub fn detect() -> Option<WindowsHostDirectories> {
+        if !is_wsl() {
+            return None;
+        }
+        let user_profile = env::var("USERPROFILE").ok();
+        WindowsHostDirectories::detect_with(DEFAULT_MOUNT_ROOT, user_profile.as_deref())
+    }
+
+    /// Like `detect`, but assuming WSL, with drives mounted below `mount_root`
+    /// and the given value of `$USERPROFILE`.
+    pub fn detect_with<P: Into<PathBuf>>(mount_root: P, user_profile: Option<&str>) -> Option<WindowsHostDirectories> {
+        let mount_root = mount_root.into();
+        if let Some(user_profile) = user_profile.and_then(|profile| windows_profile(&mount_root, profile)) {
+            return Some(WindowsHostDirectories::from_profile(mount_root, &user_profile));
+        }
+        let mut profiles = fs::read_dir(mount_root.join("c/Users"))
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| !NON_PROFILE_DIRS.contains(&name.as_str()));
+        match (profiles.next(), profiles.next()) {
+            (Some(name), None) => {
+                let user_profile = format!("C:\\Users\\{}", name);
+                Some(WindowsHostDirectories::from_profile(mount_root, &user_profile))
+            }
+            _ => None,
+        }
+    }
+
+    /// Computes the folders of the Windows user with the given profile, e. g.
+    /// `C:\Users\Eve`, with drives mounted below `mount_root`, e. g. `/mnt`.
+    pub fn from_profile<P: Into<PathBuf>>(mount_root: P, user_profile: &str) -> WindowsHostDirectories {
+        WindowsHostDirectories { mount_root: mount_root.into(), folders: PlatformFolders::new(user_profile) }
+    }
+
+    /// Sets the Windows path of a known folder, e. g. a `Documents` folder redirected to another drive.
+    pub fn with_known_folder<P: Into<PathBuf>>(mut self, folder: KnownFolder, path: P) -> WindowsHostDirectories {
+        self.folders = self.folders.with_known_folder(folder, path);
+        self
+    }
+
+    /// Returns the directory below which the Windows drives are mounted.
+    pub fn mount_root(&self) -> &Path {
+        self.mount_root.as_path()
+    }
+
+    /// Translates a Windows path like `D:\Games` to the path of the mounted drive,
+    /// e. g. `/mnt/d/Games`.
+    ///
+    /// Returns `None` for paths without a drive letter, e. g. network shares.
+    pub fn to_linux_path(&self, windows_path: &Path) -> Option<PathBuf> {
+        map_drive_path(windows_path, |letter| self.mount_root.join(letter.to_string()))
+    }
+
+    /// Returns the Linux path of the Windows directory of the given kind, see `BaseDirectories::get`.
+    pub fn get(&self, kind: DirectoryKind) -> Option<PathBuf> {
+        Platform::Windows
+            .base_dir(kind, &self.folders)
+            .and_then(|dir| self.to_linux_path(&dir))
+    }
+
+    /// Returns the Linux path of `{FOLDERID_Profile}`, e. g. `/mnt/c/Users/Eve`.
+    pub fn home_dir(&self) -> Option<PathBuf> {
+        self.get(DirectoryKind::Home)
+    }
+
+    /// Returns the Linux path of `{FOLDERID_Desktop}`, e. g. `/mnt/c/Users/Eve/Desktop`.
+    pub fn desktop_dir(&self) -> Option<PathBuf> {
+        self.get(DirectoryKind::Desktop)
+    }
+
+    /// Returns the Linux path of `{FOLDERID_Documents}`, e. g. `/mnt/c/Users/Eve/Documents`.
+    pub fn document_dir(&self) -> Option<PathBuf> {
+        self.get(DirectoryKind::Document)
+    }
+
+    /// Returns the Linux path of `{FOLDERID_Downloads}`, e. g. `/mnt/c/Users/Eve/Downloads`.
+    pub fn download_dir(&self) -> Option<PathBuf> {
+        self.get(DirectoryKind::Download)
+    }
+
+    /// Returns the Linux path of `{FOLDERID_Music}`, e. g. `/mnt/c/Users/Eve/Music`.
+    pub fn audio_dir(&self) -> Option<PathBuf> {
+        self.get(DirectoryKind::Audio)
+    }
+
+    /// Returns the Linux path of `{FOLDERID_Pictures}`, e. g. `/mnt/c/Users/Eve/Pictures`.
+    pub fn picture_dir(&self) -> Option<PathBuf> {
+        self.get(DirectoryKind::Picture)
+    }
+
+    /// Returns the Linux path of `{FOLDERID_Videos}`, e. g. `/mnt/c/Users/Eve/Videos`.
+    pub fn video_dir(&self) -> Option<PathBuf> {
+        self.get(DirectoryKind::Video)
+    }
+}
+
+/// Translates a Windows path like `C:\Users` to a host path, below the
+/// directory `drive_root` returns for the lowercase drive letter.
+pub fn map_drive_path<F: Fn(char) -> PathBuf>(windows_path: &Path, drive_root: F) -> Option<PathBuf> {
+    let windows_path = windows_path.to_string_lossy();
+    let letter = drive_letter(&windows_path)?;
+    let path = windows_path[2..]
+        .split('\\')
+        .filter(|component| !component.is_empty())
+        .fold(drive_root(letter), |path, component| path.join(component));
+    Some(path)
+}
+
+/// Returns the Windows path of a profile given as a Windows path like `C:\Users\Eve`
+/// or as a path of the mounted drive like `/mnt/c/Users/Eve`.
+fn windows_profile(mount_root: &Path, user_profile: &str) -> Option<String> {
+    if drive_letter(user_profile).is_some() {
+        return Some(user_profile.to_string());
+    }
+    let mut components = Path::new(user_profile).strip_prefix(mount_root).ok()?.iter();
+    let drive = components.next()?.to_str()?;
+    let letter = drive_letter(&format!("{}:", drive))?;
+    let profile = components.fold(format!("{}:", letter.to_ascii_uppercase()), |path, component| {
+        format!("{}\\{}", path, component.to_string_lossy())
+    });
+    Some(profile)
+}
+
+/// Returns the lowercase drive letter of a Windows path like `C:\Users`.
+fn drive_letter(windows_path: &str) -> Option<char> {
+    let mut chars = windows_path.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(letter), Some(':'), None) | (Some(letter), Some(':'), Some('\\')) if letter.is_ascii_alphabetic() => {
+            Some(letter.to_ascii_lowercase())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use kind::DirectoryKind;
+    use layout::KnownFolder;
+    use wsl::is_wsl_with;
+    use wsl::WindowsHostDirectories;
+
+    #[test]
+    fn test_windows_host_directories() {
+        assert!(is_wsl_with(Some("Ubuntu"), None));
+        assert!(is_wsl_with(None, Some("Linux version 4.4.0-19041-Microsoft (Microsoft@Microsoft.com)")));
+        assert!(is_wsl_with(None, Some("Linux version 5.15.90.1-microsoft-standard-WSL2")));
+        assert!(!is_wsl_with(Some(""), Some("Linux version 6.1.0-18-amd64 (debian-kernel@lists.debian.org)")));
+
+        let dirs = WindowsHostDirectories::from_profile("/mnt", r"C:\Users\Eve")
+            .with_known_folder(KnownFolder::Documents, r"D:\Eve\Documents");
+        assert_eq!(dirs.download_dir(), Some(PathBuf::from("/mnt/c/Users/Eve/Downloads")));
+        assert_eq!(dirs.document_dir(), Some(PathBuf::from("/mnt/d/Eve/Documents")));
+        assert_eq!(dirs.get(DirectoryKind::DataRoaming), Some(PathBuf::from("/mnt/c/Users/Eve/AppData/Roaming")));
+        assert_eq!(dirs.to_linux_path(Path::new(r"\\server\share")), None);
+
+        let root = env::temp_dir().join(format!("directories-wsl-{}", ::std::process::id()));
+        fs::create_dir_all(root.join("c/Users/Public")).unwrap();
+        fs::create_dir_all(root.join("c/Users/Eve")).unwrap();
+        fs::create_dir_all(root.join("c/Users/defaultuser0")).unwrap();
+        fs::create_dir_all(root.join("c/Users/WDAGUtilityAccount")).unwrap();
+        fs::write(root.join("c/Users/desktop.ini"), "").unwrap();
+        let detected = WindowsHostDirectories::detect_with(&root, None).unwrap();
+        assert_eq!(detected.home_dir(), Some(root.join("c/Users/Eve")));
+        let explicit = WindowsHostDirectories::detect_with(&root, Some(r"E:\Profiles\Eve")).unwrap();
+        assert_eq!(explicit.home_dir(), Some(root.join("e/Profiles/Eve")));
+        let translated = root.join("c/Users/Eve");
+        let translated = WindowsHostDirectories::detect_with(&root, translated.to_str()).unwrap();
+        assert_eq!(translated.home_dir(), Some(root.join("c/Users/Eve")));
+        assert_eq!(translated.document_dir(), Some(root.join("c/Users/Eve/Documents")));
+        fs::create_dir_all(root.join("c/Users/Mallory")).unwrap();
+        assert_eq!(WindowsHostDirectories::detect_with(&root, None), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
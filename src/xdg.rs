@@ -0,0 +1,165 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use kind::DirectoryKind;
+use provenance::Explanation;
+use provenance::Rejected;
+use provenance::RejectionReason;
+use provenance::Source;
+
+/// The XDG base directory variables, as understood by `lin::xdg_base_dir` and
+/// passed on to `Platform::Linux` by other backends.
+pub const XDG_BASE_DIR_VARIABLES: [&str; 5] =
+    ["XDG_CACHE_HOME", "XDG_CONFIG_HOME", "XDG_DATA_HOME", "XDG_BIN_HOME", "XDG_RUNTIME_DIR"];
+
+/// Explains how the directory of the given kind is resolved, given the explanation of the home directory.
+///
+/// Variables are read through `lookup` and files such as `user-dirs.dirs` through
+/// `read_file`. No directory is resolved if the home directory is unknown.
+pub fn explain_with_home<F, R>(kind: DirectoryKind, lookup: &F, read_file: &R, home: &Explanation) -> Explanation
+where
+    F: Fn(&str) -> Option<String>,
+    R: Fn(&Path) -> Option<String>,
+{
+    let home_dir = match home.path() {
+        Some(home_dir) => home_dir,
+        None if kind == DirectoryKind::Home => return home.clone(),
+        None => return Explanation::new(kind, None, None, Vec::new()),
+    };
+    let mut rejected = Vec::new();
+    let data_dir = || env_dir("XDG_DATA_HOME", lookup, &mut Vec::new()).unwrap_or_else(|| home_dir.join(".local/share"));
+    let (path, source) = match kind {
+        DirectoryKind::Home => return home.clone(),
+        DirectoryKind::Cache => env_dir_or("XDG_CACHE_HOME", lookup, &mut rejected, home_dir.join(".cache")),
+        DirectoryKind::Config => env_dir_or("XDG_CONFIG_HOME", lookup, &mut rejected, home_dir.join(".config")),
+        DirectoryKind::Data | DirectoryKind::DataRoaming => {
+            env_dir_or("XDG_DATA_HOME", lookup, &mut rejected, home_dir.join(".local/share"))
+        }
+        DirectoryKind::Executable => {
+            let mut new_dir = data_dir();
+            new_dir.pop();
+            new_dir.push("bin");
+            env_dir_or("XDG_BIN_HOME", lookup, &mut rejected, new_dir)
+        }
+        DirectoryKind::Preference | DirectoryKind::DataLow => (None, None),
+        DirectoryKind::ProgramData | DirectoryKind::UserProgram | DirectoryKind::SystemFont => (None, None),
+        DirectoryKind::Runtime => match env_dir("XDG_RUNTIME_DIR", lookup, &mut rejected) {
+            Some(dir) => (Some(dir), Some(Source::EnvVar(String::from("XDG_RUNTIME_DIR")))),
+            None => (None, None),
+        },
+        DirectoryKind::Font => (Some(data_dir().join("fonts")), Some(Source::Default)),
+        DirectoryKind::Audio => user_dir("MUSIC", lookup, read_file, home_dir, &mut rejected),
+        DirectoryKind::Desktop => user_dir("DESKTOP", lookup, read_file, home_dir, &mut rejected),
+        DirectoryKind::Document => user_dir("DOCUMENTS", lookup, read_file, home_dir, &mut rejected),
+        DirectoryKind::Download => user_dir("DOWNLOAD", lookup, read_file, home_dir, &mut rejected),
+        DirectoryKind::Picture => user_dir("PICTURES", lookup, read_file, home_dir, &mut rejected),
+        DirectoryKind::Public => user_dir("PUBLICSHARE", lookup, read_file, home_dir, &mut rejected),
+        DirectoryKind::Template => user_dir("TEMPLATES", lookup, read_file, home_dir, &mut rejected),
+        DirectoryKind::Video => user_dir("VIDEOS", lookup, read_file, home_dir, &mut rejected),
+    };
+    Explanation::new(kind, path, source, rejected)
+}
+
+/// Reads the absolute path in `var`, recording the value as rejected if it is empty or relative.
+pub fn env_dir<F: Fn(&str) -> Option<String>>(var: &str, lookup: &F, rejected: &mut Vec<Rejected>) -> Option<PathBuf> {
+    let value = lookup(var)?;
+    let reason = if value.is_empty() {
+        RejectionReason::Empty
+    } else if Path::new(&value).is_absolute() {
+        return Some(PathBuf::from(value));
+    } else {
+        RejectionReason::NotAbsolute
+    };
+    rejected.push(Rejected::new(Source::EnvVar(String::from(var)), &value, reason));
+    None
+}
+
+fn env_dir_or<F: Fn(&str) -> Option<String>>(var: &str, lookup: &F, rejected: &mut Vec<Rejected>, default: PathBuf) -> (Option<PathBuf>, Option<Source>) {
+    match env_dir(var, lookup, rejected) {
+        Some(dir) => (Some(dir), Some(Source::EnvVar(String::from(var)))),
+        None => (Some(default), Some(Source::Default)),
+    }
+}
+
+/// Reads the user directory `name` from `$XDG_CONFIG_HOME/user-dirs.dirs`.
+///
+/// Like xdg-user-dir, this falls back to the variable `XDG_<name>_DIR` if the
+/// file does not assign the directory, then to `~/Desktop` for the desktop and
+/// to the home directory for every other user directory.
+fn user_dir<F, R>(name: &str, lookup: &F, read_file: &R, home_dir: &Path, rejected: &mut Vec<Rejected>) -> (Option<PathBuf>, Option<Source>)
+where
+    F: Fn(&str) -> Option<String>,
+    R: Fn(&Path) -> Option<String>,
+{
+    let config_dir = env_dir("XDG_CONFIG_HOME", lookup, &mut Vec::new()).unwrap_or_else(|| home_dir.join(".config"));
+    let file = config_dir.join("user-dirs.dirs");
+    let mut found = None;
+    if let Some(content) = read_file(&file) {
+        for entry in parse_user_dirs_lines(&content, home_dir) {
+            if entry.name != name {
+                continue;
+            }
+            let source = Source::File { path: file.clone(), line: entry.line };
+            match entry.value {
+                Ok(dir) => found = Some((dir, source)),
+                Err(reason) => rejected.push(Rejected::new(source, &entry.raw, reason)),
+            }
+        }
+    }
+    if found.is_none() {
+        let var = format!("XDG_{}_DIR", name);
+        found = env_dir(&var, lookup, rejected).map(|dir| (dir, Source::EnvVar(var)));
+    }
+    match found {
+        Some((dir, source)) => (Some(dir), Some(source)),
+        None if name == "DESKTOP" => (Some(home_dir.join("Desktop")), Some(Source::Default)),
+        None => (Some(home_dir.to_path_buf()), Some(Source::Default)),
+    }
+}
+
+/// A single `XDG_<NAME>_DIR=...` assignment of a `user-dirs.dirs` file.
+pub struct UserDirLine {
+    pub line: usize,
+    pub name: String,
+    pub raw: String,
+    pub value: Result<PathBuf, RejectionReason>,
+}
+
+/// Parses the assignments of a `user-dirs.dirs` file, including invalid ones.
+pub fn parse_user_dirs_lines(content: &str, home_dir: &Path) -> Vec<UserDirLine> {
+    let mut lines = Vec::new();
+    for (index, line) in content.lines().map(str::trim).enumerate() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().unwrap_or("");
+        let raw = parts.next().unwrap_or("");
+        if !key.starts_with("XDG_") || !key.ends_with("_DIR") || key.len() <= "XDG__DIR".len() {
+            continue;
+        }
+        let value = if raw.len() < 2 || !raw.starts_with('"') || !raw.ends_with('"') {
+            Err(RejectionReason::Malformed)
+        } else {
+            let value = &raw[1..raw.len() - 1];
+            if value.is_empty() {
+                Err(RejectionReason::Empty)
+            } else if value == "$HOME" {
+                Ok(home_dir.to_path_buf())
+            } else if let Some(relative) = value.strip_prefix("$HOME/") {
+                Ok(home_dir.join(relative))
+            } else if value.starts_with('/') {
+                Ok(PathBuf::from(value))
+            } else {
+                Err(RejectionReason::NotAbsolute)
+            }
+        };
+        lines.push(UserDirLine {
+            line: index + 1,
+            name: String::from(&key["XDG_".len()..key.len() - "_DIR".len()]),
+            raw: String::from(raw),
+            value,
+        });
+    }
+    lines
+}